@@ -2,10 +2,75 @@ use proc_macro::TokenStream;
 
 use quote::quote;
 use syn::{
+    ext::IdentExt,
     parse::{self, Parse, ParseStream},
-    parse_macro_input, Error, Ident, ItemFn, ReturnType, Type, Visibility,
+    parse_macro_input, Ident, ItemFn, LitStr, ReturnType, Token, Type, TypeTraitObject, Visibility,
 };
 
+use crate::error;
+
+/// The options given to the attribute, e.g. `#[critic::fixture(name = "replica")]`
+#[derive(Default)]
+struct FixtureOptions {
+    /// The name used to select this fixture when there are multiple fixtures of the same type
+    name: Option<LitStr>,
+    /// Whether this fixture takes priority over other fixtures for consumers in the same module
+    module_override: bool,
+    /// The profile this fixture is an alternative provider for
+    profile: Option<LitStr>,
+    /// The trait object this fixture is provided as (instead of its concrete type)
+    provides: Option<TypeTraitObject>,
+}
+
+impl Parse for FixtureOptions {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let mut options = FixtureOptions::default();
+
+        while !input.is_empty() {
+            // `override` is a reserved keyword so can't be parsed as a normal identifier
+            let option = Ident::parse_any(input)?;
+
+            match option.to_string().as_str() {
+                "name" => {
+                    input.parse::<Token![=]>()?;
+                    options.name = Some(input.parse()?);
+                }
+                "override" => options.module_override = true,
+                "profile" => {
+                    input.parse::<Token![=]>()?;
+                    options.profile = Some(input.parse()?);
+                }
+                "provides" => {
+                    input.parse::<Token![=]>()?;
+                    options.provides = match input.parse()? {
+                        Type::TraitObject(provides) => Some(provides),
+                        other => {
+                            return Err(error(
+                                other,
+                                "fixtures can only be provided as a trait object",
+                                "use `dyn`, e.g. `provides = dyn Trait`",
+                            ))
+                        }
+                    };
+                }
+                _ => {
+                    return Err(error(
+                        &option,
+                        &format!("unknown fixture option `{option}`"),
+                        "the supported options are `name = \"...\"`, `override`, `profile = \"...\"` and `provides = dyn Trait`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
+
 struct FixtureProducer {
     producer_name: Ident,
     fixture_type: Type,
@@ -18,26 +83,35 @@ impl Parse for FixtureProducer {
 
         // TODO: maybe get rid of this check?
         if !matches!(function.vis, Visibility::Inherited) {
-            return Err(
-                Error::new_spanned(
-                    function.vis,
-                    "The visibility of the producer function is irrelevent, only the visibility of the returned fixture matters"
-                )
-            );
+            return Err(error(
+                &function.vis,
+                "the visibility of the producer function is irrelevant, only the visibility of the returned fixture matters",
+                "remove the visibility",
+            ));
+        }
+
+        if let Some(asyncness) = &function.sig.asyncness {
+            return Err(error(
+                asyncness,
+                "producer functions can't be `async`",
+                "remove `async` and block on the future inside the producer",
+            ));
         }
 
         if !function.sig.inputs.is_empty() {
-            return Err(Error::new_spanned(
-                function.sig.inputs,
-                "Producer functions are not allowed to accept any arguments (currently)",
+            return Err(error(
+                &function.sig.inputs,
+                "producer functions are not allowed to accept any arguments (currently)",
+                "remove the arguments and create what the fixture needs inside the producer",
             ));
         }
 
         let return_type = match function.sig.output.clone() {
             ReturnType::Default => {
-                return Err(Error::new_spanned(
-                    function.sig.output,
-                    "Producer functions must return a fixture",
+                return Err(error(
+                    &function.sig,
+                    "producer functions must return a fixture",
+                    "add the fixture as the return type, e.g. `-> MyFixture`",
                 ));
             }
             ReturnType::Type(_, return_type) => return_type,
@@ -51,7 +125,8 @@ impl Parse for FixtureProducer {
     }
 }
 
-pub fn fixture(input: TokenStream) -> TokenStream {
+pub fn fixture(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr as FixtureOptions);
     let producer = parse_macro_input!(input as FixtureProducer);
 
     let producer_name = producer.producer_name;
@@ -63,17 +138,46 @@ pub fn fixture(input: TokenStream) -> TokenStream {
         producer_name.span(),
     );
 
-    let runnable_fn = crate::runnable_fn::wrapper(vec![], true, producer_name.clone());
+    // Fixtures are stored behind an `Arc` (so tests can take a clone of it), a fixture provided as
+    // a trait object as `Arc<dyn Trait>` so that it can be extracted without knowing the concrete
+    // type
+    let fixture_type = match options.provides {
+        Some(provides) => Type::TraitObject(provides),
+        None => fixture_type,
+    };
+    let provider = quote! {
+        fn provide_shared() -> ::std::sync::Arc<#fixture_type> {
+            ::std::sync::Arc::new(#producer_name())
+        }
+    };
+
+    let runnable_fn = match crate::runnable_fn::wrapper(vec![], true, quote!(provide_shared)) {
+        Ok(runnable_fn) => runnable_fn,
+        Err(err) => return err.into_compile_error().into(),
+    };
+
+    let fixture_name = match options.name {
+        Some(name) => quote! { ::std::option::Option::Some(#name) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let module_override = options.module_override;
+    let profile = match options.profile {
+        Some(profile) => quote! { ::std::option::Option::Some(#profile.to_string()) },
+        None => quote! { ::std::option::Option::None },
+    };
 
     quote! {
         #[::critic::__internal::linkme::distributed_slice(crate::__critic_test_internals::CRITIC_INTERNAL_FIXTURES)]
         fn #fixture_config_name() -> ::critic::__internal::FixtureConfig {
+            #provider
             #runnable_fn
 
             critic::__internal::FixtureConfig {
                 inputs,
-                output: ::std::any::TypeId::of::<#fixture_type>(),
+                output: ::critic::__internal::FixtureRef::of::<#fixture_type>(#fixture_name),
                 runnable_fn: &runnable_wrapper,
+                module_override: #module_override,
+                profile: #profile,
                 module_path: ::std::module_path!().to_string(),
                 name: stringify!(#producer_name).to_string(),
             }
@@ -81,14 +185,4 @@ pub fn fixture(input: TokenStream) -> TokenStream {
 
         #producer_function
     }.into()
-    // quote! {
-    //     impl critic::__internal::Fixture for #fixture_type {
-    //         fn produce() -> Self {
-    //             #producer_name()
-    //         }
-    //     }
-    //
-    //     #producer_function
-    // }
-    // .into()
 }
@@ -1,15 +1,21 @@
 use proc_macro::TokenStream;
+use quote::ToTokens;
 
 mod fixture;
 mod runnable_fn;
 mod test;
 
+/// An error pointing at `tokens` that also suggests how to fix it.
+fn error(tokens: impl ToTokens, message: &str, help: &str) -> syn::Error {
+    syn::Error::new_spanned(tokens, format!("{message}\n\nhelp: {help}"))
+}
+
 #[proc_macro_attribute]
-pub fn fixture(_attr: TokenStream, input: TokenStream) -> TokenStream {
-    fixture::fixture(input)
+pub fn fixture(attr: TokenStream, input: TokenStream) -> TokenStream {
+    fixture::fixture(attr, input)
 }
 
 #[proc_macro_attribute]
-pub fn test(_attr: TokenStream, input: TokenStream) -> TokenStream {
-    test::test(input)
+pub fn test(attr: TokenStream, input: TokenStream) -> TokenStream {
+    test::test(attr, input)
 }
@@ -1,24 +1,244 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{Ident, PatType, Type};
+use syn::{FnArg, GenericArgument, Ident, LitStr, PatType, PathArguments, Signature, Type};
 
-pub fn wrapper(inputs: Vec<PatType>, output: bool, test_fn_name: Ident) -> TokenStream {
-    let ((safe_input_names, _input_names), input_types): ((Vec<_>, Vec<_>), Vec<_>) = inputs
-        .iter()
-        .enumerate()
-        .map(|(i, pat)| {
-            (
-                (
-                    Ident::new(&format!("input{i}"), Span::call_site()),
-                    pat.pat.clone(),
+use crate::error;
+
+/// The attribute used on an input to select a named fixture: `#[fixture("name")]`
+const FIXTURE_ATTRIBUTE: &str = "fixture";
+
+/// Finds the name of the fixture selected by an input with `#[fixture("name")]`.
+fn fixture_name(input: &PatType) -> syn::Result<Option<LitStr>> {
+    let mut name = None;
+
+    for attr in &input.attrs {
+        if attr.path.is_ident(FIXTURE_ATTRIBUTE) {
+            if name.is_some() {
+                return Err(error(
+                    attr,
+                    "an input can only select a single fixture",
+                    "remove all but one of the `#[fixture(\"name\")]` attributes",
+                ));
+            }
+
+            name = Some(attr.parse_args::<LitStr>().map_err(|_| {
+                error(
+                    attr,
+                    "expected the name of a fixture",
+                    "select a fixture by its name: `#[fixture(\"name\")]`",
+                )
+            })?);
+        }
+    }
+
+    Ok(name)
+}
+
+/// The type argument of `ty` if it is `wrapper<T>`, e.g. `Arc<T>` or `Option<T>`.
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if path.qself.is_some() || segment.ident != wrapper {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// How an input is taken out of the dependencies given to the runnable
+struct Access {
+    /// The variant of `InputRef`
+    variant: TokenStream,
+    /// The method of `Dependencies` taking the guard
+    extractor: TokenStream,
+    /// The method of the guard extracting the input
+    guard_extractor: TokenStream,
+    /// The type of the fixture
+    fixture_type: Type,
+    /// The input is moved out of its guard (instead of borrowed from it)
+    owned: bool,
+    /// An `Option<...>` input is `None` (instead of an error) when no fixture provides it
+    optional: bool,
+}
+
+impl Access {
+    fn of(ty: &Type) -> Access {
+        if let Some(inner) = generic_inner(ty, "Option") {
+            return Access {
+                optional: true,
+                ..Access::of(inner)
+            };
+        }
+
+        let (variant, extractor, guard_extractor, fixture_type, owned) = match ty {
+            Type::Reference(reference) if reference.mutability.is_some() => (
+                quote! { Exclusive },
+                quote! { exclusive },
+                quote! { guard_extract },
+                reference.elem.as_ref(),
+                false,
+            ),
+            Type::Reference(reference) => (
+                quote! { Shared },
+                quote! { shared },
+                quote! { guard_extract },
+                reference.elem.as_ref(),
+                false,
+            ),
+            // An `Arc<T>` is given a clone of the `Arc` the fixture is stored in and a `Factory<T>`
+            // creates instances with the producer of the fixture
+            ty => match (generic_inner(ty, "Arc"), generic_inner(ty, "Factory")) {
+                (Some(inner), _) => (
+                    quote! { SharedArc },
+                    quote! { shared },
+                    quote! { guard_extract_arc },
+                    inner,
+                    false,
+                ),
+                (_, Some(inner)) => (
+                    quote! { Factory },
+                    quote! { factory },
+                    quote! { guard_extract },
+                    inner,
+                    false,
+                ),
+                (None, None) => (
+                    quote! { Owned },
+                    quote! { owned },
+                    quote! { guard_extract },
+                    ty,
+                    true,
                 ),
-                pat.ty.clone(),
-            )
-        })
-        .unzip();
+            },
+        };
+
+        Access {
+            variant,
+            extractor,
+            guard_extractor,
+            fixture_type: fixture_type.clone(),
+            owned,
+            optional: false,
+        }
+    }
+}
+
+/// Rejects the inputs that can't be injected.
+fn check_input(input: &PatType) -> syn::Result<()> {
+    let ty = generic_inner(&input.ty, "Option").unwrap_or(&input.ty);
+    let ty = match ty {
+        Type::Reference(reference) => {
+            if let Some(lifetime) = &reference.lifetime {
+                return Err(error(
+                    lifetime,
+                    "lifetimes are not supported on inputs",
+                    "remove the lifetime, fixtures outlive the tests using them",
+                ));
+            }
+
+            reference.elem.as_ref()
+        }
+        ty => generic_inner(ty, "Arc")
+            .or_else(|| generic_inner(ty, "Factory"))
+            .unwrap_or(ty),
+    };
+    if let Type::ImplTrait(impl_trait) = ty {
+        return Err(error(
+            impl_trait,
+            "`impl Trait` inputs are not supported",
+            "take a trait object (`&dyn Trait`) provided by a fixture with `provides = dyn Trait`",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Removes the critic specific attributes from the inputs of a function so that it can be emitted
+/// as normal code.
+pub fn strip_input_attributes(sig: &mut Signature) {
+    for input in &mut sig.inputs {
+        if let FnArg::Typed(input) = input {
+            input
+                .attrs
+                .retain(|attr| !attr.path.is_ident(FIXTURE_ATTRIBUTE));
+        }
+    }
+}
+
+/// Generates `runnable_wrapper` (which extracts the dependencies and calls `runnable_fn`) and the
+/// list of `inputs` it expects.
+pub fn wrapper(
+    inputs: Vec<PatType>,
+    output: bool,
+    runnable_fn: TokenStream,
+) -> syn::Result<TokenStream> {
+    for input in &inputs {
+        check_input(input)?;
+    }
+
+    let names = inputs
+        .iter()
+        .map(fixture_name)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let accesses: Vec<_> = inputs.iter().map(|input| Access::of(&input.ty)).collect();
+
+    // The inputs are passed positionally, so any pattern in the signature (`(a, b): &(A, B)`,
+    // `mut x: Foo`, ...) binds them exactly as written
+    let mut input_refs = Vec::new();
+    let mut guards = Vec::new();
+    let mut arguments = Vec::new();
+    for (i, (access, name)) in accesses.iter().zip(&names).enumerate() {
+        let guard = Ident::new(&format!("input{i}"), Span::call_site());
+        let Access {
+            variant,
+            extractor,
+            guard_extractor,
+            fixture_type,
+            owned,
+            optional,
+        } = access;
+
+        let fixture_name = match name {
+            Some(name) => quote! { ::std::option::Option::Some(#name) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let fixture_ref = quote! {
+            ::critic::__internal::FixtureRef::of::<#fixture_type>(#fixture_name)
+        };
+        let input_ref = quote! { ::critic::__internal::InputRef::#variant(#fixture_ref) };
+
+        if *optional {
+            input_refs.push(quote! { ::critic::__internal::InputRef::optional(#input_ref) });
+            guards.push(quote! {
+                let mut #guard = runnable_input
+                    .dependencies
+                    .contains(#fixture_ref)
+                    .then(|| runnable_input.dependencies.#extractor(#fixture_ref));
+            });
+            arguments.push(if *owned {
+                quote! { #guard.map(|guard| guard.#guard_extractor()) }
+            } else {
+                quote! { #guard.as_mut().map(|guard| guard.#guard_extractor()) }
+            });
+        } else {
+            input_refs.push(input_ref);
+            guards.push(quote! {
+                let mut #guard = runnable_input.dependencies.#extractor(#fixture_ref);
+            });
+            arguments.push(quote! { #guard.#guard_extractor() });
+        }
+    }
 
     let test_fn_run = quote! {
-        #test_fn_name ( #(#safe_input_names.guard_extract()),* )
+        #runnable_fn ( #(#arguments),* )
     };
 
     let run_fn = if output {
@@ -33,38 +253,11 @@ pub fn wrapper(inputs: Vec<PatType>, output: bool, test_fn_name: Ident) -> Token
         }
     };
 
-    let inner_type: Vec<_> = input_types
-        .clone()
-        .into_iter()
-        .map(|ty| match ty.as_ref() {
-            Type::Reference(r) => r.elem.clone(),
-            _ => ty,
-        })
-        .collect();
-
-    let (input_ref_variant, extractor_name): (Vec<_>, Vec<_>) = input_types
-        .into_iter()
-        .map(|ty| match *ty {
-            Type::Reference(reference) => {
-                if reference.lifetime.is_some() {
-                    panic!("lifetimes are unsupported on inputs references");
-                }
-
-                if reference.mutability.is_some() {
-                    (quote! { Exclusive }, quote! { exclusive })
-                } else {
-                    (quote! { Shared }, quote! { shared })
-                }
-            }
-            _ => (quote! { Owned }, quote! { owned }),
-        })
-        .unzip();
-
-    quote! {
+    Ok(quote! {
         fn runnable_wrapper(
             mut runnable_input: ::critic::__internal::RunnableInput,
         ) -> ::std::result::Result<(), Box<dyn ::std::error::Error>> {
-            #( let mut #safe_input_names = runnable_input.dependencies.#extractor_name (::std::any::TypeId::of::<#inner_type>());)*
+            #(#guards)*
 
             #run_fn
 
@@ -72,7 +265,7 @@ pub fn wrapper(inputs: Vec<PatType>, output: bool, test_fn_name: Ident) -> Token
         }
 
         let inputs = vec![
-            #(::critic::__internal::InputRef::#input_ref_variant (::std::any::TypeId::of::<#inner_type>())),*
+            #(#input_refs),*
         ];
-    }
+    })
 }
@@ -1,43 +1,248 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse::Parse, parse_macro_input, FnArg, Ident, ItemFn};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Error, FnArg, GenericParam, Ident, ItemFn, ReturnType, Token, Type, TypeParam,
+};
+
+use crate::error;
+
+/// The options given to the attribute, e.g. `#[critic::test(types(A, B))]`
+#[derive(Default)]
+struct TestOptions {
+    /// The concrete types a generic test should be instantiated with
+    types: Option<Punctuated<Type, Token![,]>>,
+}
+
+impl Parse for TestOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = TestOptions::default();
+
+        while !input.is_empty() {
+            let option: Ident = input.parse()?;
+
+            match option.to_string().as_str() {
+                "types" => {
+                    let content;
+                    parenthesized!(content in input);
+                    let types = content.parse_terminated(Type::parse)?;
+                    if types.is_empty() {
+                        return Err(error(
+                            option,
+                            "`types(...)` must list at least one type",
+                            "list the types to run the test with, e.g. `types(A, B)`",
+                        ));
+                    }
+                    options.types = Some(types);
+                }
+                _ => {
+                    return Err(error(
+                        &option,
+                        &format!("unknown test option `{option}`"),
+                        "the only supported option is `types(...)`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
 
 struct TestFunction {
     body: ItemFn,
 }
 
 impl Parse for TestFunction {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
         let body: ItemFn = input.parse()?;
 
-        // TODO: checks
+        if let Some(asyncness) = &body.sig.asyncness {
+            return Err(error(
+                asyncness,
+                "tests can't be `async`",
+                "remove `async` and block on the future inside the test",
+            ));
+        }
+
+        if let ReturnType::Type(_, ty) = &body.sig.output {
+            return Err(error(
+                ty,
+                "tests can't return a value",
+                "remove the return type and panic (e.g. with `assert!` or `unwrap`) to fail the test",
+            ));
+        }
 
         Ok(TestFunction { body })
     }
 }
 
-pub fn test(input: TokenStream) -> TokenStream {
+/// Finds the single type parameter of a generic test (if it is generic at all).
+/// Writes `ty` the way it is usually written (e.g. `Wrap<u8>` instead of the `Wrap < u8 >` of its
+/// tokens), only keeping the spaces that separate two words such as in `dyn Trait`.
+fn type_name(ty: &Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut name = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ' ' {
+            name.push(c);
+        } else if name.ends_with(is_word) && chars.peek().copied().is_some_and(is_word) {
+            name.push(' ');
+        }
+    }
+
+    name
+}
+
+fn generic_type_param(test_body: &ItemFn) -> syn::Result<Option<TypeParam>> {
+    let mut type_param = None;
+
+    for param in &test_body.sig.generics.params {
+        match param {
+            GenericParam::Type(param) if type_param.is_none() => type_param = Some(param.clone()),
+            GenericParam::Type(param) => {
+                return Err(error(
+                    param,
+                    "generic tests can only have a single type parameter",
+                    "combine the parameters into a single trait, or write a test per combination",
+                ))
+            }
+            GenericParam::Const(param) => {
+                return Err(error(
+                    param,
+                    "const generics are not supported on tests",
+                    "use a type parameter with an associated const instead",
+                ))
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+
+    Ok(type_param)
+}
+
+pub fn test(attr: TokenStream, input: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr as TestOptions);
     let test_function = parse_macro_input!(input as TestFunction);
-    let test_body = test_function.body;
+
+    expand(options, test_function)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(options: TestOptions, test_function: TestFunction) -> syn::Result<TokenStream2> {
+    let mut test_body = test_function.body;
     let test_name = test_body.sig.ident.clone();
-    let test_config_name = Ident::new(
-        &format!("__critic_internal_{test_name}_config"),
-        test_name.span(),
-    );
 
     let arg = test_body
         .sig
         .inputs
         .iter()
         .map(|arg| match arg {
-            FnArg::Typed(arg) => arg.clone(),
-            FnArg::Receiver(_) => panic!("tests are not allowed to take in self"),
+            FnArg::Typed(arg) => Ok(arg.clone()),
+            FnArg::Receiver(receiver) => Err(error(
+                receiver,
+                "tests can't take `self`",
+                "make the test a free function and ask for the value as a fixture, e.g. `value: &MyFixture`",
+            )),
         })
-        .collect::<Vec<_>>();
+        .collect::<syn::Result<Vec<_>>>()?;
+    crate::runnable_fn::strip_input_attributes(&mut test_body.sig);
+
+    let configs = match (generic_type_param(&test_body)?, options.types) {
+        (None, None) => {
+            let test_config_name = Ident::new(
+                &format!("__critic_internal_{test_name}_config"),
+                test_name.span(),
+            );
+
+            vec![test_config(
+                &test_config_name,
+                quote!(#test_name),
+                quote!(stringify!(#test_name).to_string()),
+                arg,
+            )?]
+        }
+        (Some(type_param), Some(types)) => {
+            let generic_name = type_param.ident;
+
+            types
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| {
+                    let test_config_name = Ident::new(
+                        &format!("__critic_internal_{test_name}_{i}_config"),
+                        test_name.span(),
+                    );
 
-    let basic_runnable = crate::runnable_fn::wrapper(arg, false, test_name.clone());
+                    // The alias makes the generic parameter resolve to the concrete type inside
+                    // the config function, so inputs such as `&S` become injectable fixtures.
+                    let name = format!("{test_name}::<{}>", type_name(ty));
+                    let config = test_config(
+                        &test_config_name,
+                        quote!(#test_name::<#ty>),
+                        quote!(#name.to_string()),
+                        arg.clone(),
+                    )?;
 
-    quote!(
+                    Ok(quote! {
+                        const _: () = {
+                            #[allow(non_camel_case_types)]
+                            type #generic_name = #ty;
+
+                            #config
+                        };
+                    })
+                })
+                .collect::<syn::Result<_>>()?
+        }
+        (None, Some(types)) => {
+            return Err(error(
+                types,
+                "`types(...)` can only be used on tests with a type parameter",
+                "remove `types(...)`, or add a type parameter to the test, e.g. `fn test<T>()`",
+            ))
+        }
+        (Some(type_param), None) => {
+            return Err(error(
+                &type_param,
+                "generic tests need a list of types to run with",
+                &format!(
+                    "list the types to use for `{}`: `#[critic::test(types(...))]`",
+                    type_param.ident
+                ),
+            ))
+        }
+    };
+
+    Ok(quote!(
+        #(#configs)*
+
+        #test_body
+    ))
+}
+
+/// Generates the function registering a single test with critic
+fn test_config(
+    test_config_name: &Ident,
+    test_fn: TokenStream2,
+    name: TokenStream2,
+    arg: Vec<syn::PatType>,
+) -> syn::Result<TokenStream2> {
+    let basic_runnable = crate::runnable_fn::wrapper(arg, false, test_fn)?;
+
+    Ok(quote!(
         #[critic::__internal::linkme::distributed_slice(crate::__critic_test_internals::CRITIC_INTERNAL_TESTS)]
         fn #test_config_name() -> critic::__internal::TestConfig {
             #basic_runnable
@@ -49,11 +254,8 @@ pub fn test(input: TokenStream) -> TokenStream {
                 exclusion_group: None,
                 runnable_fn: &runnable_wrapper,
                 module_path: ::std::module_path!().to_string(),
-                name: stringify!(#test_name).to_string(),
+                name: #name,
             }
         }
-
-        #test_body
-    )
-    .into()
+    ))
 }
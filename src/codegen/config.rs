@@ -1,13 +1,51 @@
 use std::any::TypeId;
 
-use crate::engine::runnable::RunnableFn;
+use crate::engine::{runnable::RunnableFn, test_definition};
 
-#[derive(Clone, Debug, Copy)]
-// TODO: I think only T = TypeId is ever used so maybe get rid of generics
-pub enum InputRef<T = TypeId> {
+/// Identifies a fixture, either the one requested by an input or the one a fixture provides.
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+pub struct FixtureRef {
+    /// The type of the fixture
+    pub type_id: TypeId,
+    /// The name of the type of the fixture (for diagnostics)
+    pub type_name: &'static str,
+    /// The name given to the fixture to tell apart multiple fixtures of the same type
+    pub name: Option<&'static str>,
+}
+
+impl FixtureRef {
+    pub fn of<T: ?Sized + 'static>(name: Option<&'static str>) -> Self {
+        FixtureRef {
+            type_id: TypeId::of::<T>(),
+            type_name: std::any::type_name::<T>(),
+            name,
+        }
+    }
+}
+
+impl std::fmt::Display for FixtureRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "`{}` named \"{}\"", self.type_name, name),
+            None => write!(f, "`{}`", self.type_name),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+// TODO: I think only T = FixtureRef is ever used so maybe get rid of generics
+pub enum InputRef<T = FixtureRef> {
     Shared(T),
+    /// An `Arc<...>` input, which shares the fixture like `Shared` but can keep it alive after the
+    /// test finishes
+    SharedArc(T),
     Exclusive(T),
     Owned(T),
+    /// A `Factory<...>` input, which creates instances with the producer of the fixture instead of
+    /// accessing the one shared instance
+    Factory(T),
+    /// An `Option<...>` input, which is `None` instead of an error when no fixture provides it
+    Optional(Box<InputRef<T>>),
 }
 
 impl<T> InputRef<T> {
@@ -23,18 +61,55 @@ impl<T> InputRef<T> {
         InputRef::Owned(inner)
     }
 
+    pub fn optional(input: InputRef<T>) -> Self {
+        InputRef::Optional(Box::new(input))
+    }
+
     pub fn inner(&self) -> &T {
         use InputRef::*;
-        match &self {
-            Shared(inner) | Exclusive(inner) | Owned(inner) => inner,
+        match self {
+            Shared(inner) | SharedArc(inner) | Exclusive(inner) | Owned(inner) | Factory(inner) => {
+                inner
+            }
+            Optional(input) => input.inner(),
+        }
+    }
+
+    /// The input without the `Optional` wrapper, which is how the fixture is accessed when it is
+    /// provided.
+    pub fn required(&self) -> &InputRef<T> {
+        match self {
+            InputRef::Optional(input) => input.required(),
+            input => input,
+        }
+    }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self, InputRef::Optional(_))
+    }
+
+    /// How the input accesses the fixture: `shared`, `arc`, `exclusive`, `owned` or `factory`.
+    pub fn access(&self) -> &'static str {
+        use InputRef::*;
+        match self {
+            Shared(_) => "shared",
+            SharedArc(_) => "arc",
+            Exclusive(_) => "exclusive",
+            Owned(_) => "owned",
+            Factory(_) => "factory",
+            Optional(input) => input.access(),
         }
     }
 }
 
-impl InputRef<TypeId> {
-    pub fn id(&self) -> TypeId {
+impl InputRef<FixtureRef> {
+    pub fn fixture(&self) -> FixtureRef {
         *self.inner()
     }
+
+    pub fn id(&self) -> TypeId {
+        self.inner().type_id
+    }
 }
 
 /// All the configuration for a test
@@ -60,12 +135,83 @@ pub struct FixtureConfig {
     /// The inputs (fixtures) that this fixture should receive
     /// NOTE: THIS IS NOT CURRENTLY SUPPORTED
     pub inputs: Vec<InputRef>,
-    /// The type of the output of this fixture
-    pub output: TypeId,
+    /// The type (and optional name) of the output of this fixture
+    pub output: FixtureRef,
     /// The runnable function
     pub runnable_fn: RunnableFn,
+    /// Whether this fixture takes priority over other providers of the same type for consumers
+    /// inside its module (and its submodules)
+    pub module_override: bool,
+    /// The profile this fixture is an alternative provider for, it is only used when that
+    /// profile is active
+    pub profile: Option<String>,
     /// The full path of the module the producer function is in
     pub module_path: String,
     /// The name of the producer function
     pub name: String,
 }
+
+impl TestConfig {
+    pub fn fqn(&self) -> String {
+        test_definition::fqn(&self.module_path, &self.name)
+    }
+}
+
+impl FixtureConfig {
+    pub fn fqn(&self) -> String {
+        test_definition::fqn(&self.module_path, &self.name)
+    }
+}
+
+#[cfg(test)]
+impl TestConfig {
+    /// A test that does nothing, for testing the engine.
+    pub(crate) fn stub(module_path: &str, name: &str, inputs: Vec<InputRef>) -> Self {
+        TestConfig {
+            should_panic: false,
+            subprocess: false,
+            inputs,
+            exclusion_group: None,
+            runnable_fn: &|_| Ok(()),
+            module_path: module_path.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl FixtureConfig {
+    /// A default fixture providing `output` that produces nothing, for testing the engine.
+    pub(crate) fn stub(module_path: &str, name: &str, output: FixtureRef) -> Self {
+        FixtureConfig {
+            inputs: Vec::new(),
+            output,
+            runnable_fn: &|_| Ok(()),
+            module_override: false,
+            profile: None,
+            module_path: module_path.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Fixtures shared by the tests of the engine.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::FixtureRef;
+
+    pub(crate) struct Db;
+    pub(crate) struct Pool;
+
+    pub(crate) fn db() -> FixtureRef {
+        FixtureRef::of::<Db>(None)
+    }
+
+    pub(crate) fn named_db(name: &'static str) -> FixtureRef {
+        FixtureRef::of::<Db>(Some(name))
+    }
+
+    pub(crate) fn pool() -> FixtureRef {
+        FixtureRef::of::<Pool>(None)
+    }
+}
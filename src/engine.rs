@@ -1,26 +1,46 @@
-use std::{any::TypeId, collections::HashMap};
+use std::collections::HashMap;
 
 use uuid::Uuid;
 
-use crate::codegen::config::{FixtureConfig, InputRef, TestConfig};
+use crate::{
+    codegen::config::{FixtureConfig, InputRef, TestConfig},
+    options::{Format, GraphFormat, Options},
+    report::{Reporter, RunInfo, Summary},
+};
 
-use self::runner::Runner;
+use self::{
+    dry_run::ExplainError,
+    runner::Runner,
+    validate::{validate, ValidationError},
+};
 
+pub mod capture;
 pub mod dependencies;
+pub mod dry_run;
+pub mod factory;
 pub mod fixture;
+pub mod list;
+pub mod records;
+pub mod resolver;
 pub mod runnable;
 pub mod runner;
 pub mod test_definition;
+pub mod validate;
 
 pub struct EngineConfig {
     pub tests: HashMap<Uuid, TestConfig>,
-    pub fixtures: HashMap<TypeId, FixtureConfig>,
+    pub fixtures: HashMap<Uuid, FixtureConfig>,
+    /// The fixture each input of a test or fixture resolved to (in the same order as the inputs),
+    /// `None` for an optional input that no fixture provides
+    pub resolved_inputs: HashMap<Uuid, Vec<Option<Uuid>>>,
     pub groups: HashMap<String, TestGroup>,
     pub labels: HashMap<Label, Vec<Uuid>>,
     // pub fixture_nodes: HashMap<TypeId, Uuid>,
 }
 
 pub struct Engine {
+    config: EngineConfig,
+    run: RunInfo,
     runner: Runner,
 }
 
@@ -34,8 +54,8 @@ pub enum Label {
     /// A label that is the user namespace
     User(String),
     /// A label for tracking dependencies
-    /// Typically used to indicate that a node takes an input with this TypeID
-    Dependency(TypeId),
+    /// Typically used to indicate that a node takes an input from the fixture with this ID
+    Dependency(Uuid),
 }
 
 pub struct TestGroup {
@@ -55,18 +75,30 @@ impl TestGroup {
 }
 
 impl Engine {
-    pub(crate) fn new(tests: &[fn() -> TestConfig], fixtures: &[fn() -> FixtureConfig]) -> Self {
+    pub(crate) fn new(
+        tests: &[fn() -> TestConfig],
+        fixtures: &[fn() -> FixtureConfig],
+        options: &Options,
+    ) -> Result<Self, ValidationError> {
         let fixtures: HashMap<_, _> = fixtures
             .iter()
             .map(|definer| definer())
-            .map(|config| (dbg!(config.output), config))
+            .filter(|config| {
+                config.profile.is_none() || config.profile.as_deref() == options.profile.as_deref()
+            })
+            .map(|config| (Uuid::new_v4(), config))
             .collect();
 
+        let tests: Vec<_> = tests.iter().map(|definer| definer()).collect();
+        let test_count = tests.len();
         let tests: HashMap<_, _> = tests
-            .iter()
-            .map(|definer| definer())
+            .into_iter()
+            .filter(|config| options.matches_filters(&config.module_path, &config.name))
             .map(|config| (Uuid::new_v4(), config))
             .collect();
+        let filtered_out = test_count - tests.len();
+
+        let resolved_inputs = validate(&tests, &fixtures)?;
 
         let mut groups = HashMap::new();
         let mut labels: HashMap<Label, Vec<Uuid>> = HashMap::new();
@@ -86,16 +118,21 @@ impl Engine {
                     .push(*test_id);
             }
 
-            for input in &config.inputs {
+            for (input, fixture_id) in config.inputs.iter().zip(&resolved_inputs[test_id]) {
+                let Some(fixture_id) = fixture_id else {
+                    continue;
+                };
+
                 use InputRef::*;
-                match input {
-                    Shared(id) | Exclusive(id) => {
+                match input.required() {
+                    Shared(_) | SharedArc(_) | Exclusive(_) => {
                         labels
-                            .entry(Label::Dependency(*id))
+                            .entry(Label::Dependency(*fixture_id))
                             .or_default()
                             .push(*test_id);
                     }
-                    Owned(_) => {}
+                    Owned(_) | Factory(_) => {}
+                    Optional(_) => unreachable!("`required` unwraps optional inputs"),
                 }
             }
         }
@@ -103,16 +140,90 @@ impl Engine {
         let config = EngineConfig {
             tests,
             fixtures,
+            resolved_inputs,
             groups,
             labels,
             // fixture_nodes,
         };
         let runner = Runner::new(&config);
 
-        Engine { runner }
+        let run = RunInfo {
+            // TODO: update once the runner is multithreaded
+            threads: 1,
+            seed: None,
+            filters: options.filters.clone(),
+            filtered_out,
+            profile: options.profile.clone(),
+            capture: !options.nocapture,
+        };
+
+        Ok(Engine {
+            config,
+            run,
+            runner,
+        })
+    }
+
+    /// Every test and fixture in `format` (see [`list::list`]), for `--list`.
+    pub fn list(&self, format: Format) -> String {
+        list::list(&self.config, format)
+    }
+
+    /// The waves the tests would run in (for `--dry-run`).
+    pub fn dry_run(&mut self) -> String {
+        dry_run::dry_run(&mut self.runner)
+    }
+
+    /// How `test` would be scheduled: the fixtures it needs and what it can't run with.
+    pub fn explain(&self, test: &str) -> Result<String, ExplainError> {
+        dry_run::explain(&self.config, &self.runner, test)
+    }
+
+    /// Prints the graph of the schedule in `format` instead of running it.
+    pub fn graph(&self, format: GraphFormat) {
+        print!("{}", self.runner.graph(format));
+    }
+
+    pub fn run(&mut self, reporter: &mut dyn Reporter) -> Summary {
+        self.runner.run(&self.run, reporter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::config::{testing::db, InputRef};
+
+    /// The producer the input of the test resolves to with `profile` active.
+    fn provider(profile: Option<&str>) -> String {
+        let tests: &[fn() -> TestConfig] =
+            &[|| TestConfig::stub("app", "reads", vec![InputRef::shared(db())])];
+        let fixtures: &[fn() -> FixtureConfig] = &[
+            || FixtureConfig::stub("app", "in_memory", db()),
+            || FixtureConfig {
+                profile: Some("ci".to_string()),
+                ..FixtureConfig::stub("app", "postgres", db())
+            },
+        ];
+        let options = Options {
+            profile: profile.map(str::to_string),
+            ..Options::default()
+        };
+
+        let config = Engine::new(tests, fixtures, &options).unwrap().config;
+        let test_id = config.tests.keys().next().unwrap();
+        let fixture_id = config.resolved_inputs[test_id][0].unwrap();
+        config.fixtures[&fixture_id].name.clone()
+    }
+
+    #[test]
+    fn active_profile_picks_its_provider() {
+        assert_eq!(provider(Some("ci")), "postgres");
     }
 
-    pub fn run(&mut self) {
-        self.runner.run();
+    #[test]
+    fn falls_back_to_default_provider() {
+        assert_eq!(provider(None), "in_memory");
+        assert_eq!(provider(Some("local")), "in_memory");
     }
 }
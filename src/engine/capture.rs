@@ -0,0 +1,161 @@
+//! Captures the stdout and stderr of a test or fixture by redirecting the file descriptors of the
+//! process, so output from anything (including C code and child processes) is caught.
+
+use std::io::{self, Write};
+
+use crate::report::Output;
+
+/// Runs `f` with its stdout and stderr captured.
+///
+/// If the output can't be redirected `f` runs with its output passed through instead.
+pub(crate) fn capture<R>(f: impl FnOnce() -> R) -> (R, Output) {
+    match imp::Capture::start() {
+        Ok(capture) => {
+            let result = f();
+            let output = capture.finish().unwrap_or_else(|err| Output {
+                stderr: format!("failed to read the captured output: {err}"),
+                ..Default::default()
+            });
+
+            (result, output)
+        }
+        Err(err) => {
+            eprintln!("warning: failed to capture output: {err}");
+            (f(), Output::default())
+        }
+    }
+}
+
+/// A handle to the terminal (or whatever stdout originally was) which is unaffected by capturing.
+pub(crate) fn terminal() -> Box<dyn Write + Send> {
+    imp::terminal().unwrap_or_else(|| Box::new(io::stdout()))
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        fs::File,
+        io::{self, Read, Seek, SeekFrom, Write},
+        os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    };
+
+    use crate::report::Output;
+
+    pub(super) struct Capture {
+        stdout: Redirect,
+        stderr: Redirect,
+    }
+
+    impl Capture {
+        pub(super) fn start() -> io::Result<Self> {
+            Ok(Capture {
+                stdout: Redirect::new(libc::STDOUT_FILENO)?,
+                stderr: Redirect::new(libc::STDERR_FILENO)?,
+            })
+        }
+
+        pub(super) fn finish(self) -> io::Result<Output> {
+            Ok(Output {
+                stdout: self.stdout.finish()?,
+                stderr: self.stderr.finish()?,
+                records: Vec::new(),
+            })
+        }
+    }
+
+    /// Points `fd` at a temporary file until it is finished (or dropped).
+    struct Redirect {
+        fd: RawFd,
+        /// A duplicate of what `fd` originally pointed at
+        saved: Option<RawFd>,
+        file: File,
+    }
+
+    impl Redirect {
+        fn new(fd: RawFd) -> io::Result<Self> {
+            flush_std();
+            let file = tempfile::tempfile()?;
+
+            let saved = unsafe { libc::dup(fd) };
+            if saved == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if unsafe { libc::dup2(file.as_raw_fd(), fd) } == -1 {
+                let err = io::Error::last_os_error();
+                unsafe { libc::close(saved) };
+                return Err(err);
+            }
+
+            Ok(Redirect {
+                fd,
+                saved: Some(saved),
+                file,
+            })
+        }
+
+        fn restore(&mut self) {
+            if let Some(saved) = self.saved.take() {
+                flush_std();
+                unsafe {
+                    libc::dup2(saved, self.fd);
+                    libc::close(saved);
+                }
+            }
+        }
+
+        fn finish(mut self) -> io::Result<String> {
+            self.restore();
+
+            let mut bytes = Vec::new();
+            self.file.seek(SeekFrom::Start(0))?;
+            self.file.read_to_end(&mut bytes)?;
+
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        }
+    }
+
+    impl Drop for Redirect {
+        fn drop(&mut self) {
+            self.restore();
+        }
+    }
+
+    /// Writes out anything buffered by `std` so it ends up on the right side of a redirect.
+    fn flush_std() {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+    }
+
+    pub(super) fn terminal() -> Option<Box<dyn Write + Send>> {
+        let fd = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if fd == -1 {
+            return None;
+        }
+
+        Some(Box::new(unsafe { File::from_raw_fd(fd) }))
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io::{self, Write};
+
+    use crate::report::Output;
+
+    /// Redirecting output is only supported on unix, elsewhere it is passed straight through.
+    pub(super) struct Capture;
+
+    impl Capture {
+        pub(super) fn start() -> io::Result<Self> {
+            Ok(Capture)
+        }
+
+        pub(super) fn finish(self) -> io::Result<Output> {
+            Ok(Output::default())
+        }
+    }
+
+    pub(super) fn terminal() -> Option<Box<dyn Write + Send>> {
+        None
+    }
+}
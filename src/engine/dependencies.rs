@@ -1,17 +1,30 @@
 use std::{
-    any::{Any, TypeId},
+    any::Any,
     collections::HashMap,
     ops::{Deref, DerefMut},
+    sync::Arc,
 };
 
 use parking_lot::{RwLockReadGuard, RwLockWriteGuard};
 
+use super::{
+    factory::Factory,
+    runnable::{self, RunnableFn},
+};
+use crate::codegen::config::FixtureRef;
+
 /// Represents a dynamic group of dependencies for a test or fixture
+///
+/// A runnable can take the same fixture more than once (e.g. `Counter` and `&Counter`, or `&Counter`
+/// and `Arc<Counter>`), so each fixture has a list of values that the inputs take in order. Only
+/// shared and owned accesses can be repeated, validation rejects a `&mut` input of a fixture that
+/// another input of the same runnable borrows.
 #[derive(Default)]
 pub struct Dependencies<'dep> {
-    owned: HashMap<TypeId, Box<dyn Any>>,
-    shared: HashMap<TypeId, RwLockReadGuard<'dep, Box<dyn Any>>>,
-    exclusive: HashMap<TypeId, RwLockWriteGuard<'dep, Box<dyn Any>>>,
+    owned: HashMap<FixtureRef, Vec<Box<dyn Any>>>,
+    shared: HashMap<FixtureRef, Vec<RwLockReadGuard<'dep, Box<dyn Any>>>>,
+    exclusive: HashMap<FixtureRef, Vec<RwLockWriteGuard<'dep, Box<dyn Any>>>>,
+    factories: HashMap<FixtureRef, Vec<RunnableFn>>,
 }
 
 pub struct ReadGuard<'a> {
@@ -19,12 +32,22 @@ pub struct ReadGuard<'a> {
 }
 
 impl<'a> ReadGuard<'a> {
-    pub fn guard_extract<T: 'static>(&self) -> &T {
+    fn arc<T: ?Sized + 'static>(&self) -> &Arc<T> {
         self.inner
             .deref()
             .downcast_ref()
             .expect("failed to downcast into inner type")
     }
+
+    pub fn guard_extract<T: ?Sized + 'static>(&self) -> &T {
+        self.arc()
+    }
+
+    /// Extracts a clone of the `Arc` the fixture is stored in, which keeps the fixture alive for as
+    /// long as the test needs it (e.g. in a spawned thread).
+    pub fn guard_extract_arc<T: ?Sized + 'static>(&self) -> Arc<T> {
+        Arc::clone(self.arc())
+    }
 }
 
 pub struct WriteGuard<'a> {
@@ -32,11 +55,25 @@ pub struct WriteGuard<'a> {
 }
 
 impl<'a> WriteGuard<'a> {
-    pub fn guard_extract<T: 'static>(&mut self) -> &mut T {
-        self.inner
+    pub fn guard_extract<T: ?Sized + 'static>(&mut self) -> &mut T {
+        let arc: &mut Arc<T> = self
+            .inner
             .deref_mut()
             .downcast_mut()
-            .expect("failed to downcast into inner type")
+            .expect("failed to downcast into inner type");
+
+        Arc::get_mut(arc).expect("the runner checks that no test keeps the fixture in an `Arc`")
+    }
+}
+
+/// Useful for codegen even though it isn't really a "guard"
+pub struct FactoryGuard {
+    producer: RunnableFn,
+}
+
+impl FactoryGuard {
+    pub fn guard_extract<T: 'static>(&self) -> Factory<T> {
+        Factory::new(self.producer)
     }
 }
 
@@ -47,10 +84,7 @@ pub struct OwnedGuard {
 
 impl OwnedGuard {
     pub fn guard_extract<T: 'static>(self) -> T {
-        *self
-            .inner
-            .downcast()
-            .expect("failed to downcast owned type")
+        runnable::into_instance(self.inner)
     }
 }
 
@@ -59,43 +93,73 @@ impl<'dep> Dependencies<'dep> {
         Default::default()
     }
 
-    pub(crate) fn add_owned<T: 'static>(&mut self, val: T) {
-        self.owned
-            .insert(TypeId::of::<T>(), Box::new(val) as Box<dyn Any>);
+    /// Adds a fresh instance of a fixture (the output of [`runnable::produce`]) for an owned input.
+    pub(crate) fn add_owned(&mut self, fixture: FixtureRef, val: Box<dyn Any>) {
+        self.owned.entry(fixture).or_default().push(val);
     }
 
-    pub(crate) fn add_shared(&mut self, type_id: TypeId, val: RwLockReadGuard<'dep, Box<dyn Any>>) {
-        self.shared.insert(type_id, val);
+    pub(crate) fn add_shared(
+        &mut self,
+        fixture: FixtureRef,
+        val: RwLockReadGuard<'dep, Box<dyn Any>>,
+    ) {
+        self.shared.entry(fixture).or_default().push(val);
     }
 
     pub(crate) fn add_exclusive(
         &mut self,
-        type_id: TypeId,
+        fixture: FixtureRef,
         val: RwLockWriteGuard<'dep, Box<dyn Any>>,
     ) {
-        self.exclusive.insert(type_id, val);
+        self.exclusive.entry(fixture).or_default().push(val);
+    }
+
+    pub(crate) fn add_factory(&mut self, fixture: FixtureRef, producer: RunnableFn) {
+        self.factories.entry(fixture).or_default().push(producer);
     }
 
-    pub fn owned(&mut self, type_id: TypeId) -> OwnedGuard {
-        self.owned
-            .remove(&type_id)
+    /// Whether `fixture` was provided, which it always is unless it is the fixture of an optional
+    /// input that no fixture provides.
+    pub fn contains(&self, fixture: FixtureRef) -> bool {
+        fn has<T>(values: &HashMap<FixtureRef, Vec<T>>, fixture: &FixtureRef) -> bool {
+            values.get(fixture).is_some_and(|values| !values.is_empty())
+        }
+
+        has(&self.owned, &fixture)
+            || has(&self.shared, &fixture)
+            || has(&self.exclusive, &fixture)
+            || has(&self.factories, &fixture)
+    }
+
+    /// Takes the value of the next input that takes `fixture`.
+    fn take<T>(values: &mut HashMap<FixtureRef, Vec<T>>, fixture: &FixtureRef) -> Option<T> {
+        let values = values.get_mut(fixture)?;
+        (!values.is_empty()).then(|| values.remove(0))
+    }
+
+    pub fn owned(&mut self, fixture: FixtureRef) -> OwnedGuard {
+        Self::take(&mut self.owned, &fixture)
             .map(|val| OwnedGuard { inner: val })
             .expect("the owned type should exist")
     }
 
-    pub fn shared(&mut self, type_id: TypeId) -> ReadGuard<'dep> {
-        self.shared
-            .remove(&type_id)
+    pub fn shared(&mut self, fixture: FixtureRef) -> ReadGuard<'dep> {
+        Self::take(&mut self.shared, &fixture)
             .map(|val| ReadGuard { inner: val })
             .expect("the shared type should exist")
     }
 
-    pub fn exclusive(&mut self, type_id: TypeId) -> WriteGuard<'dep> {
-        self.exclusive
-            .remove(&type_id)
+    pub fn exclusive(&mut self, fixture: FixtureRef) -> WriteGuard<'dep> {
+        Self::take(&mut self.exclusive, &fixture)
             .map(|val| WriteGuard { inner: val })
             .expect("the exclusive type should exist")
     }
+
+    pub fn factory(&mut self, fixture: FixtureRef) -> FactoryGuard {
+        Self::take(&mut self.factories, &fixture)
+            .map(|producer| FactoryGuard { producer })
+            .expect("the factory should exist")
+    }
 }
 // impl<'dep> Dependencies<'dep> {
 //     pub fn add<T: 'dep>(&mut self, val: T) {
@@ -0,0 +1,332 @@
+//! `--dry-run` and `--explain`: how the tests would be scheduled, without running anything.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Write},
+};
+
+use uuid::Uuid;
+
+use super::{runner::Runner, EngineConfig};
+use crate::codegen::config::InputRef;
+
+/// The names of every test and fixture node
+fn names(runner: &Runner) -> HashMap<Uuid, String> {
+    let plan = runner.plan();
+    let tests = plan.tests.iter().map(|test| (test.id, test.fqn()));
+    let fixtures = plan
+        .fixtures
+        .iter()
+        .map(|fixture| (fixture.id, fixture.fqn()));
+
+    tests.chain(fixtures).collect()
+}
+
+/// Renders the waves the tests and fixtures would run in (see [`Runner::waves`]).
+pub fn dry_run(runner: &mut Runner) -> String {
+    let names = names(runner);
+    let fixture_count = runner.plan().fixtures.len();
+    let test_count = runner.plan().tests.len();
+    let is_fixture: Vec<_> = runner.plan().fixtures.iter().map(|f| f.id).collect();
+
+    let mut out = String::new();
+    let waves = runner.waves();
+    for (i, wave) in waves.iter().enumerate() {
+        let mut wave: Vec<_> = wave
+            .iter()
+            .map(|id| {
+                let kind = if is_fixture.contains(id) {
+                    "fixture"
+                } else {
+                    "test"
+                };
+                (&names[id], kind)
+            })
+            .collect();
+        wave.sort();
+
+        let _ = writeln!(out, "wave {}:", i + 1);
+        for (name, kind) in wave {
+            let _ = writeln!(out, "    {kind} {name}");
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "\n{test_count} tests and {fixture_count} fixtures in {} waves",
+        waves.len()
+    );
+
+    out
+}
+
+/// The test given to `--explain` doesn't match exactly one test.
+#[derive(Debug)]
+pub enum ExplainError {
+    NotFound(String),
+    Ambiguous { test: String, matches: Vec<String> },
+}
+
+impl fmt::Display for ExplainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExplainError::NotFound(test) => write!(f, "no test matches `{test}`"),
+            ExplainError::Ambiguous { test, matches } => {
+                writeln!(f, "`{test}` matches more than one test:")?;
+                for name in matches {
+                    writeln!(f, "    {name}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ExplainError {}
+
+/// Finds the test called `name` (with or without the crate), or the only test containing `name`.
+fn find_test(config: &EngineConfig, name: &str) -> Result<Uuid, ExplainError> {
+    let mut tests: Vec<_> = config
+        .tests
+        .iter()
+        .map(|(id, test)| (*id, test.fqn()))
+        .collect();
+    tests.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+    let exact = tests
+        .iter()
+        .find(|(_, fqn)| fqn == name || fqn.split_once("::").is_some_and(|(_, path)| path == name));
+    if let Some((id, _)) = exact {
+        return Ok(*id);
+    }
+
+    let matches: Vec<_> = tests
+        .into_iter()
+        .filter(|(_, fqn)| fqn.contains(name))
+        .collect();
+    match matches.as_slice() {
+        [] => Err(ExplainError::NotFound(name.to_string())),
+        [(id, _)] => Ok(*id),
+        _ => Err(ExplainError::Ambiguous {
+            test: name.to_string(),
+            matches: matches.into_iter().map(|(_, fqn)| fqn).collect(),
+        }),
+    }
+}
+
+/// Why the tests `a` and `b` can't run at the same time.
+fn exclusion_reasons(config: &EngineConfig, a: &Uuid, b: &Uuid) -> Vec<String> {
+    let mut reasons = Vec::new();
+    let (Some(test_a), Some(test_b)) = (config.tests.get(a), config.tests.get(b)) else {
+        return reasons;
+    };
+
+    if let (Some(group_a), Some(group_b)) = (&test_a.exclusion_group, &test_b.exclusion_group) {
+        if group_a == group_b {
+            reasons.push(format!("both are in the exclusion group `{group_a}`"));
+        }
+    }
+
+    let inputs = |id: &Uuid| {
+        config.tests[id]
+            .inputs
+            .iter()
+            .zip(&config.resolved_inputs[id])
+            .filter_map(|(input, fixture_id)| Some((input, fixture_id.as_ref()?)))
+            .collect::<Vec<_>>()
+    };
+    for (input_a, fixture_a) in inputs(a) {
+        for (input_b, fixture_b) in inputs(b) {
+            if fixture_a != fixture_b {
+                continue;
+            }
+
+            let fixture = &config.fixtures[fixture_a];
+            for (test, input) in [(test_a, input_a), (test_b, input_b)] {
+                if let InputRef::Exclusive(_) = input.required() {
+                    reasons.push(format!(
+                        "{} uses {} (from {}) exclusively",
+                        test.fqn(),
+                        fixture.output,
+                        fixture.fqn()
+                    ));
+                }
+            }
+        }
+    }
+
+    reasons.dedup();
+    reasons
+}
+
+/// Renders the fixtures `test` depends on (transitively) and every node it can't run at the same
+/// time as, with why.
+pub fn explain(config: &EngineConfig, runner: &Runner, test: &str) -> Result<String, ExplainError> {
+    let test_id = find_test(config, test)?;
+    let names = names(runner);
+    let schedule = runner.schedule();
+
+    let mut parents: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for (parent, child) in schedule.dependencies() {
+        parents.entry(child).or_default().push(parent);
+    }
+    parents
+        .values_mut()
+        .for_each(|parents| parents.sort_by_key(|id| &names[id]));
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}: test", names[&test_id]);
+
+    let _ = writeln!(out, "fixtures:");
+    if !parents.contains_key(&test_id) {
+        let _ = writeln!(out, "    none");
+    }
+    let mut stack: Vec<_> = parents
+        .get(&test_id)
+        .into_iter()
+        .flatten()
+        .rev()
+        .map(|id| (*id, 1))
+        .collect();
+    while let Some((id, depth)) = stack.pop() {
+        let fixture = &config.fixtures[&id];
+        let access = config.tests[&test_id]
+            .inputs
+            .iter()
+            .zip(&config.resolved_inputs[&test_id])
+            .find(|(_, fixture_id)| **fixture_id == Some(id))
+            .filter(|_| depth == 1)
+            .map(|(input, _)| format!(" ({})", input.access()))
+            .unwrap_or_default();
+
+        let _ = writeln!(
+            out,
+            "{}{}{access} <- {}",
+            "    ".repeat(depth),
+            fixture.output,
+            names[&id]
+        );
+        stack.extend(
+            parents
+                .get(&id)
+                .into_iter()
+                .flatten()
+                .rev()
+                .map(|parent| (*parent, depth + 1)),
+        );
+    }
+
+    let mut excluded: Vec<_> = schedule
+        .exclusions()
+        .into_iter()
+        .filter_map(|(a, b)| match (a == test_id, b == test_id) {
+            (true, _) => Some(b),
+            (_, true) => Some(a),
+            _ => None,
+        })
+        .collect();
+    excluded.sort_by_key(|id| &names[id]);
+
+    let _ = writeln!(out, "mutually excludes:");
+    if excluded.is_empty() {
+        let _ = writeln!(out, "    none");
+    }
+    for other in excluded {
+        let _ = writeln!(out, "    {}", names[&other]);
+        for reason in exclusion_reasons(config, &test_id, &other) {
+            let _ = writeln!(out, "        {reason}");
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        codegen::config::{
+            testing::{db, pool},
+            FixtureConfig, InputRef, TestConfig,
+        },
+        engine::Engine,
+        options::Options,
+    };
+
+    fn engine(tests: &[fn() -> TestConfig]) -> Engine {
+        let fixtures: &[fn() -> FixtureConfig] = &[
+            || FixtureConfig::stub("app", "db", db()),
+            || FixtureConfig::stub("app", "pool", pool()),
+        ];
+        Engine::new(tests, fixtures, &Options::default()).unwrap()
+    }
+
+    #[test]
+    fn dry_run_lists_waves() {
+        let mut engine = engine(&[
+            || TestConfig::stub("app", "reads", vec![InputRef::shared(db())]),
+            || TestConfig::stub("app", "standalone", vec![]),
+        ]);
+
+        assert_eq!(
+            engine.dry_run(),
+            "wave 1:\n    fixture app::db\n    test app::standalone\nwave 2:\n    test app::reads\n\
+             \n2 tests and 1 fixtures in 2 waves\n"
+        );
+    }
+
+    #[test]
+    fn explains_fixtures_and_exclusions() {
+        let engine = engine(&[
+            || TestConfig {
+                exclusion_group: Some("serial".to_string()),
+                ..TestConfig::stub(
+                    "app",
+                    "writes",
+                    vec![InputRef::shared(db()), InputRef::exclusive(pool())],
+                )
+            },
+            || TestConfig::stub("app", "reads_pool", vec![InputRef::shared(pool())]),
+            || TestConfig {
+                exclusion_group: Some("serial".to_string()),
+                ..TestConfig::stub("app", "serial", vec![])
+            },
+            || TestConfig::stub("app", "reads_db", vec![InputRef::shared(db())]),
+        ]);
+
+        assert_eq!(
+            engine.explain("writes").unwrap(),
+            format!(
+                "app::writes: test\n\
+                 fixtures:\n    `{db}` (shared) <- app::db\n    `{pool}` (exclusive) <- app::pool\n\
+                 mutually excludes:\n    app::reads_pool\n        \
+                 app::writes uses `{pool}` (from app::pool) exclusively\n    app::serial\n        \
+                 both are in the exclusion group `serial`\n",
+                db = db().type_name,
+                pool = pool().type_name,
+            )
+        );
+        assert_eq!(
+            engine.explain("app::serial").unwrap(),
+            "app::serial: test\nfixtures:\n    none\nmutually excludes:\n    app::writes\n        \
+             both are in the exclusion group `serial`\n"
+        );
+    }
+
+    #[test]
+    fn explain_needs_exactly_one_test() {
+        let engine = engine(&[
+            || TestConfig::stub("app", "reads_db", vec![]),
+            || TestConfig::stub("app", "reads_pool", vec![]),
+        ]);
+
+        assert_eq!(
+            engine.explain("writes").unwrap_err().to_string(),
+            "no test matches `writes`"
+        );
+        assert_eq!(
+            engine.explain("reads").unwrap_err().to_string(),
+            "`reads` matches more than one test:\n    app::reads_db\n    app::reads_pool\n"
+        );
+    }
+}
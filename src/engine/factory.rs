@@ -0,0 +1,33 @@
+use std::{any::type_name, marker::PhantomData};
+
+use super::runnable::{self, RunnableFn};
+
+/// Creates fresh instances of the fixture `T` on demand, for tests that need more than one (e.g.
+/// several clients talking to each other).
+///
+/// Take it as an input (`clients: critic::Factory<Client>`) and call [`Factory::create`]. Each
+/// instance is produced by the fixture providing `T` (chosen like for any other input) and
+/// belongs to the test, so it is torn down (dropped) when the test finishes at the latest.
+pub struct Factory<T> {
+    producer: RunnableFn,
+    fixture: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> Factory<T> {
+    pub(crate) fn new(producer: RunnableFn) -> Self {
+        Factory {
+            producer,
+            fixture: PhantomData,
+        }
+    }
+
+    /// Runs the producer of `T` to create a new instance.
+    ///
+    /// Panics (failing the test) if the producer fails.
+    pub fn create(&self) -> T {
+        match runnable::produce(self.producer) {
+            Ok(output) => runnable::into_instance(output),
+            Err(err) => panic!("the producer of `{}` failed: {err}", type_name::<T>()),
+        }
+    }
+}
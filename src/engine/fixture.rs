@@ -1,3 +1,10 @@
+/// A type that can be produced as a fixture.
+///
+/// It is not implemented by `#[critic::fixture]` to detect missing fixtures at compile time: a type
+/// can have several providers (named, per profile or as a module override), and stable Rust can't
+/// require "at least one impl" of a marker without the impls conflicting or the bound becoming
+/// ambiguous. Missing and ambiguous fixtures are reported together by the validation in
+/// `Engine::new` before anything runs instead.
 pub trait Fixture {
     fn produce() -> Self;
 }
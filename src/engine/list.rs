@@ -0,0 +1,363 @@
+//! `--list`: lists every test and fixture (and how they are connected) instead of running them.
+
+use std::{collections::HashMap, fmt::Write};
+
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{EngineConfig, Label};
+use crate::options::{self, Format};
+
+/// Renders the tests and fixtures of `config` in `format`.
+///
+/// `terse` has a line per test in the style of libtest, `json` a single JSON document and any other
+/// format the human readable listing.
+pub fn list(config: &EngineConfig, format: Format) -> String {
+    let listing = Listing::new(config);
+
+    match format {
+        Format::Terse => listing.terse(),
+        Format::Json => listing.json(),
+        _ => listing.pretty(),
+    }
+}
+
+struct Listing<'a> {
+    config: &'a EngineConfig,
+    /// The tests sorted by name
+    tests: Vec<Uuid>,
+    /// The fixtures sorted by name
+    fixtures: Vec<Uuid>,
+    /// The labels of each test
+    labels: HashMap<Uuid, Vec<String>>,
+    /// The tests using each fixture (and how they access it)
+    consumers: HashMap<Uuid, Vec<(Uuid, &'static str)>>,
+}
+
+impl<'a> Listing<'a> {
+    fn new(config: &'a EngineConfig) -> Self {
+        let mut tests: Vec<_> = config.tests.keys().copied().collect();
+        tests.sort_by_key(|id| config.tests[id].fqn());
+        let mut fixtures: Vec<_> = config.fixtures.keys().copied().collect();
+        fixtures.sort_by_key(|id| config.fixtures[id].fqn());
+
+        let mut labels: HashMap<_, Vec<_>> = HashMap::new();
+        for (label, ids) in &config.labels {
+            let label = match label {
+                Label::User(name) => name.clone(),
+                Label::Dependency(fixture_id) => {
+                    format!("fixture:{}", config.fixtures[fixture_id].fqn())
+                }
+            };
+            for id in ids {
+                labels.entry(*id).or_default().push(label.clone());
+            }
+        }
+        labels.values_mut().for_each(|labels| labels.sort());
+
+        let mut consumers: HashMap<_, Vec<_>> = HashMap::new();
+        for test_id in &tests {
+            let inputs = config.tests[test_id].inputs.iter();
+            for (input, fixture_id) in inputs.zip(&config.resolved_inputs[test_id]) {
+                let Some(fixture_id) = fixture_id else {
+                    continue;
+                };
+                consumers
+                    .entry(*fixture_id)
+                    .or_default()
+                    .push((*test_id, input.access()));
+            }
+        }
+
+        Listing {
+            config,
+            tests,
+            fixtures,
+            labels,
+            consumers,
+        }
+    }
+
+    fn labels(&self, test_id: &Uuid) -> &[String] {
+        self.labels
+            .get(test_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn consumers(&self, fixture_id: &Uuid) -> &[(Uuid, &'static str)] {
+        self.consumers
+            .get(fixture_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Lists the tests like libtest does (so tools can run them with `--exact`), using the same
+    /// names as the libtest JSON events.
+    fn terse(&self) -> String {
+        let mut out = String::new();
+        for test_id in &self.tests {
+            let test = &self.config.tests[test_id];
+            let _ = writeln!(
+                out,
+                "{}: test",
+                options::libtest_name(&test.module_path, &test.name)
+            );
+        }
+
+        out
+    }
+
+    fn pretty(&self) -> String {
+        let mut out = String::new();
+        for test_id in &self.tests {
+            let test = &self.config.tests[test_id];
+            let _ = writeln!(out, "{}: test", test.fqn());
+
+            for (input, fixture_id) in test
+                .inputs
+                .iter()
+                .zip(&self.config.resolved_inputs[test_id])
+            {
+                let optional = if input.is_optional() {
+                    ", optional"
+                } else {
+                    ""
+                };
+                let Some(fixture_id) = fixture_id else {
+                    let _ = writeln!(
+                        out,
+                        "    {} ({}{optional}) <- none",
+                        input.fixture(),
+                        input.access()
+                    );
+                    continue;
+                };
+
+                let fixture = &self.config.fixtures[fixture_id];
+                let profile = fixture
+                    .profile
+                    .as_ref()
+                    .map(|profile| format!(" (profile {profile})"))
+                    .unwrap_or_default();
+
+                let _ = writeln!(
+                    out,
+                    "    {} ({}{optional}) <- {}{}",
+                    input.fixture(),
+                    input.access(),
+                    fixture.fqn(),
+                    profile
+                );
+            }
+            if let Some(group) = &test.exclusion_group {
+                let _ = writeln!(out, "    exclusion group: {group}");
+            }
+            if !self.labels(test_id).is_empty() {
+                let _ = writeln!(out, "    labels: {}", self.labels(test_id).join(", "));
+            }
+        }
+
+        if !self.fixtures.is_empty() {
+            out.push('\n');
+        }
+        for fixture_id in &self.fixtures {
+            let fixture = &self.config.fixtures[fixture_id];
+            let _ = writeln!(out, "{}: fixture", fixture.fqn());
+            let _ = writeln!(out, "    provides {}", fixture.output);
+            if let Some(profile) = &fixture.profile {
+                let _ = writeln!(out, "    profile: {profile}");
+            }
+            if fixture.module_override {
+                let _ = writeln!(out, "    overrides providers in {}", fixture.module_path);
+            }
+
+            let consumers: Vec<_> = self
+                .consumers(fixture_id)
+                .iter()
+                .map(|(test_id, access)| format!("{} ({access})", self.config.tests[test_id].fqn()))
+                .collect();
+            if consumers.is_empty() {
+                let _ = writeln!(out, "    unused");
+            } else {
+                let _ = writeln!(out, "    consumers: {}", consumers.join(", "));
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "\n{} tests, {} fixtures",
+            self.tests.len(),
+            self.fixtures.len()
+        );
+
+        out
+    }
+
+    fn json(&self) -> String {
+        let tests: Vec<_> = self
+            .tests
+            .iter()
+            .map(|test_id| {
+                let test = &self.config.tests[test_id];
+                let fixtures: Vec<_> = test
+                    .inputs
+                    .iter()
+                    .zip(&self.config.resolved_inputs[test_id])
+                    .map(|(input, fixture_id)| {
+                        json!({
+                            "type": input.fixture().type_name,
+                            "name": input.fixture().name,
+                            "access": input.access(),
+                            "optional": input.is_optional(),
+                            "producer": fixture_id.map(|id| self.config.fixtures[&id].fqn()),
+                        })
+                    })
+                    .collect();
+
+                json!({
+                    "name": test.fqn(),
+                    "module_path": test.module_path,
+                    "labels": self.labels(test_id),
+                    "exclusion_group": test.exclusion_group,
+                    "fixtures": fixtures,
+                })
+            })
+            .collect();
+
+        let fixtures: Vec<_> = self
+            .fixtures
+            .iter()
+            .map(|fixture_id| {
+                let fixture = &self.config.fixtures[fixture_id];
+                let consumers: Vec<_> = self
+                    .consumers(fixture_id)
+                    .iter()
+                    .map(|(test_id, access)| {
+                        json!({
+                            "test": self.config.tests[test_id].fqn(),
+                            "access": access,
+                        })
+                    })
+                    .collect();
+
+                json!({
+                    "producer": fixture.fqn(),
+                    "module_path": fixture.module_path,
+                    "type": fixture.output.type_name,
+                    "name": fixture.output.name,
+                    "profile": fixture.profile,
+                    "override": fixture.module_override,
+                    "consumers": consumers,
+                })
+            })
+            .collect();
+
+        format!("{}\n", json!({ "tests": tests, "fixtures": fixtures }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        codegen::config::{
+            testing::{db, named_db, pool},
+            FixtureConfig, InputRef, TestConfig,
+        },
+        engine::Engine,
+        options::Options,
+    };
+
+    fn listing(format: Format) -> String {
+        let tests: &[fn() -> TestConfig] = &[
+            || TestConfig {
+                exclusion_group: Some("serial".to_string()),
+                ..TestConfig::stub("app::net", "reads", vec![InputRef::shared(db())])
+            },
+            || {
+                TestConfig::stub(
+                    "app",
+                    "writes",
+                    vec![InputRef::exclusive(db()), InputRef::owned(pool())],
+                )
+            },
+        ];
+        let fixtures: &[fn() -> FixtureConfig] = &[
+            || FixtureConfig::stub("app", "db", db()),
+            || FixtureConfig::stub("app", "pool", pool()),
+            || FixtureConfig::stub("app", "replica", named_db("replica")),
+        ];
+
+        Engine::new(tests, fixtures, &Options::default())
+            .unwrap()
+            .list(format)
+    }
+
+    #[test]
+    fn lists_tests_and_fixtures() {
+        assert_eq!(
+            listing(Format::Pretty),
+            format!(
+                "app::net::reads: test\n    `{db}` (shared) <- app::db\n    \
+                 exclusion group: serial\n    labels: fixture:app::db, serial\n\
+                 app::writes: test\n    `{db}` (exclusive) <- app::db\n    \
+                 `{pool}` (owned) <- app::pool\n    labels: fixture:app::db\n\
+                 \n\
+                 app::db: fixture\n    provides `{db}`\n    \
+                 consumers: app::net::reads (shared), app::writes (exclusive)\n\
+                 app::pool: fixture\n    provides `{pool}`\n    consumers: app::writes (owned)\n\
+                 app::replica: fixture\n    provides `{db}` named \"replica\"\n    unused\n\
+                 \n2 tests, 3 fixtures\n",
+                db = db().type_name,
+                pool = pool().type_name,
+            )
+        );
+    }
+
+    #[test]
+    fn lists_tests_like_libtest() {
+        assert_eq!(listing(Format::Terse), "net::reads: test\nwrites: test\n");
+    }
+
+    #[test]
+    fn lists_as_json() {
+        let listing: Value = serde_json::from_str(&listing(Format::Json)).unwrap();
+
+        assert_eq!(
+            listing["tests"][1],
+            json!({
+                "name": "app::writes",
+                "module_path": "app",
+                "labels": ["fixture:app::db"],
+                "exclusion_group": null,
+                "fixtures": [
+                    {
+                        "type": db().type_name,
+                        "name": null,
+                        "access": "exclusive",
+                        "optional": false,
+                        "producer": "app::db",
+                    },
+                    {
+                        "type": pool().type_name,
+                        "name": null,
+                        "access": "owned",
+                        "optional": false,
+                        "producer": "app::pool",
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            listing["fixtures"][0]["consumers"],
+            json!([
+                { "test": "app::net::reads", "access": "shared" },
+                { "test": "app::writes", "access": "exclusive" },
+            ])
+        );
+        assert_eq!(listing["fixtures"][2]["consumers"], json!([]));
+    }
+}
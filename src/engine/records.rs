@@ -0,0 +1,202 @@
+//! Collects the `log` records and `tracing` events emitted while a test or fixture runs.
+//!
+//! The runner runs one node at a time, so every record emitted while a node runs (including on
+//! threads it spawned) is attributed to that node. Records emitted outside of a node, such as by
+//! a thread that outlives the test which spawned it, are written to stderr instead.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use parking_lot::Mutex;
+
+use crate::report::{Level, Record};
+
+/// The records of the node that is running (if any)
+static CURRENT: Mutex<Option<Vec<Record>>> = Mutex::new(None);
+
+/// The least severe level that is kept
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+/// Starts collecting records up to `max_level`, installing the logger and subscriber of the
+/// enabled features (unless another has been installed already).
+pub(crate) fn install(max_level: Level) {
+    MAX_LEVEL.store(max_level as u8, Ordering::Relaxed);
+
+    #[cfg(feature = "log")]
+    log_records::install(max_level);
+    #[cfg(feature = "tracing")]
+    tracing_events::install();
+}
+
+fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Runs `f` collecting the records emitted on any thread until it returns.
+pub(crate) fn collect<R>(f: impl FnOnce() -> R) -> (R, Vec<Record>) {
+    *CURRENT.lock() = Some(Vec::new());
+    let result = f();
+    let records = CURRENT.lock().take();
+
+    (result, records.unwrap_or_default())
+}
+
+/// Attributes `record` to the node that is running.
+#[cfg_attr(not(any(feature = "log", feature = "tracing")), allow(dead_code))]
+fn record(record: Record) {
+    if !enabled(record.level) {
+        return;
+    }
+
+    match CURRENT.lock().as_mut() {
+        Some(records) => records.push(record),
+        None => eprintln!("{record}"),
+    }
+}
+
+#[cfg(feature = "log")]
+mod log_records {
+    use crate::report::{Level, Record};
+
+    struct Logger;
+
+    fn level(level: log::Level) -> Level {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+
+    impl log::Log for Logger {
+        fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+            super::enabled(level(metadata.level()))
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            super::record(Record {
+                level: level(record.level()),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
+    pub(super) fn install(max_level: Level) {
+        if log::set_logger(&Logger).is_ok() {
+            log::set_max_level(match max_level {
+                Level::Error => log::LevelFilter::Error,
+                Level::Warn => log::LevelFilter::Warn,
+                Level::Info => log::LevelFilter::Info,
+                Level::Debug => log::LevelFilter::Debug,
+                Level::Trace => log::LevelFilter::Trace,
+            });
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod tracing_events {
+    use std::fmt::{self, Write};
+
+    use tracing::{
+        field::{Field, Visit},
+        Event, Metadata, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    use crate::report::{Level, Record};
+
+    /// A `tracing` layer collecting the events of each test and fixture.
+    ///
+    /// It is installed as the global subscriber when the run starts, but can also be added to a
+    /// subscriber of your own that is installed before then.
+    pub struct TracingLayer;
+
+    fn level(level: &tracing::Level) -> Level {
+        match *level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            tracing::Level::DEBUG => Level::Debug,
+            tracing::Level::TRACE => Level::Trace,
+        }
+    }
+
+    /// Formats the `message` of an event followed by its other fields.
+    #[derive(Default)]
+    struct Message {
+        message: String,
+        fields: String,
+    }
+
+    impl Visit for Message {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.message, "{value:?}");
+            } else {
+                let _ = write!(self.fields, " {}={value:?}", field.name());
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for TracingLayer {
+        fn enabled(&self, metadata: &Metadata<'_>, _: Context<'_, S>) -> bool {
+            super::enabled(level(metadata.level()))
+        }
+
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            let mut message = Message::default();
+            event.record(&mut message);
+
+            super::record(Record {
+                level: level(event.metadata().level()),
+                target: event.metadata().target().to_string(),
+                message: (message.message + &message.fields).trim_start().to_string(),
+            });
+        }
+    }
+
+    pub(super) fn install() {
+        let _ = tracing::subscriber::set_global_default(
+            tracing_subscriber::registry().with(TracingLayer),
+        );
+    }
+}
+
+#[cfg(feature = "tracing")]
+pub use tracing_events::TracingLayer;
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn warning(message: &str) -> Record {
+        Record {
+            level: Level::Warn,
+            target: "app".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn collects_records_from_spawned_threads() {
+        let ((), records) = collect(|| {
+            record(warning("on the test thread"));
+            thread::spawn(|| record(warning("on a spawned thread")))
+                .join()
+                .unwrap();
+        });
+
+        let messages: Vec<_> = records
+            .iter()
+            .map(|record| record.message.as_str())
+            .collect();
+        assert_eq!(messages, ["on the test thread", "on a spawned thread"]);
+    }
+}
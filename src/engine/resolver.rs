@@ -0,0 +1,325 @@
+use std::{any::TypeId, collections::HashMap, error::Error, fmt};
+
+use uuid::Uuid;
+
+use crate::codegen::config::{FixtureConfig, FixtureRef};
+
+/// Finds the fixture that should be given to an input.
+///
+/// The fixtures should only contain the providers of the active profile (and the default
+/// providers).
+pub struct Resolver<'a> {
+    fixtures: &'a HashMap<Uuid, FixtureConfig>,
+    /// Every fixture that provides a type
+    providers: HashMap<TypeId, Vec<Uuid>>,
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    /// No fixture provides the requested type (and name).
+    Missing {
+        fixture: FixtureRef,
+        consumer: String,
+    },
+    /// More than one fixture could satisfy the request.
+    Ambiguous {
+        fixture: FixtureRef,
+        consumer: String,
+        providers: Vec<String>,
+    },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Missing { fixture, consumer } => {
+                write!(
+                    f,
+                    "`{consumer}` requires {fixture} but no fixture provides it"
+                )
+            }
+            ResolveError::Ambiguous {
+                fixture,
+                consumer,
+                providers,
+            } => write!(
+                f,
+                "`{consumer}` requires {fixture} which is ambiguous because it is provided by {}, \
+                 use #[fixture(\"name\")] on the input to pick one",
+                providers.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for ResolveError {}
+
+impl<'a> Resolver<'a> {
+    pub fn new(fixtures: &'a HashMap<Uuid, FixtureConfig>) -> Self {
+        let mut providers: HashMap<TypeId, Vec<Uuid>> = HashMap::new();
+
+        for (id, config) in fixtures {
+            providers
+                .entry(config.output.type_id)
+                .or_default()
+                .push(*id);
+        }
+
+        Resolver {
+            fixtures,
+            providers,
+        }
+    }
+
+    /// Finds the fixture for `fixture` as requested by `consumer` (the fully qualified name of a
+    /// test) which lives in `module_path`.
+    ///
+    /// An override fixture in the closest ancestor module of the consumer takes priority over the
+    /// other providers.
+    pub fn resolve(
+        &self,
+        fixture: FixtureRef,
+        module_path: &str,
+        consumer: &str,
+    ) -> Result<Uuid, ResolveError> {
+        let providers = self
+            .providers
+            .get(&fixture.type_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let (overrides, global): (Vec<Uuid>, Vec<Uuid>) = providers
+            .iter()
+            .partition(|id| self.fixtures[id].module_override);
+
+        // Overrides are grouped by module so that the closest group with a match wins
+        let mut overrides_by_module: HashMap<&str, Vec<Uuid>> = HashMap::new();
+        for id in overrides {
+            let override_path = self.fixtures[&id].module_path.as_str();
+            if is_ancestor(override_path, module_path) {
+                overrides_by_module
+                    .entry(override_path)
+                    .or_default()
+                    .push(id);
+            }
+        }
+        let mut overrides_by_module: Vec<_> = overrides_by_module.into_iter().collect();
+        overrides_by_module.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+
+        let candidates = overrides_by_module
+            .iter()
+            .map(|(_, overrides)| self.select(overrides, fixture))
+            .find(|candidates| !candidates.is_empty())
+            .unwrap_or_else(|| self.select(&global, fixture));
+
+        match candidates.as_slice() {
+            [id] => Ok(*id),
+            [] => Err(ResolveError::Missing {
+                fixture,
+                consumer: consumer.to_string(),
+            }),
+            _ => Err(ResolveError::Ambiguous {
+                fixture,
+                consumer: consumer.to_string(),
+                providers: {
+                    let mut providers: Vec<_> = candidates
+                        .iter()
+                        .map(|id| format!("`{}`", self.fixtures[id].fqn()))
+                        .collect();
+                    providers.sort();
+                    providers
+                },
+            }),
+        }
+    }
+
+    /// Picks the providers out of `providers` that match the name of the request.
+    ///
+    /// An unnamed request prefers the unnamed providers of a type, falling back to the providers
+    /// of any name.
+    /// Providers for the active profile replace the default providers.
+    fn select(&self, providers: &[Uuid], fixture: FixtureRef) -> Vec<Uuid> {
+        let candidates = self.select_by_name(providers, fixture);
+
+        if candidates
+            .iter()
+            .any(|id| self.fixtures[id].profile.is_some())
+        {
+            candidates
+                .into_iter()
+                .filter(|id| self.fixtures[id].profile.is_some())
+                .collect()
+        } else {
+            candidates
+        }
+    }
+
+    fn select_by_name(&self, providers: &[Uuid], fixture: FixtureRef) -> Vec<Uuid> {
+        match fixture.name {
+            Some(name) => providers
+                .iter()
+                .copied()
+                .filter(|id| self.fixtures[id].output.name == Some(name))
+                .collect(),
+            None => {
+                let unnamed: Vec<Uuid> = providers
+                    .iter()
+                    .copied()
+                    .filter(|id| self.fixtures[id].output.name.is_none())
+                    .collect();
+
+                if unnamed.is_empty() {
+                    providers.to_vec()
+                } else {
+                    unnamed
+                }
+            }
+        }
+    }
+}
+
+/// Whether the module `ancestor` is `module` or one of its parents.
+fn is_ancestor(ancestor: &str, module: &str) -> bool {
+    module
+        .strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::config::testing::{db, named_db};
+
+    fn fixtures(configs: Vec<FixtureConfig>) -> HashMap<Uuid, FixtureConfig> {
+        configs
+            .into_iter()
+            .map(|config| (Uuid::new_v4(), config))
+            .collect()
+    }
+
+    /// The fully qualified name of the fixture `fixture` resolves to for a test in `module_path`.
+    fn resolve(
+        fixtures: &HashMap<Uuid, FixtureConfig>,
+        fixture: FixtureRef,
+        module_path: &str,
+    ) -> Result<String, String> {
+        Resolver::new(fixtures)
+            .resolve(fixture, module_path, &format!("{module_path}::test"))
+            .map(|id| fixtures[&id].fqn())
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn picks_the_provider_with_the_requested_name() {
+        let fixtures = fixtures(vec![
+            FixtureConfig::stub("app", "primary", db()),
+            FixtureConfig::stub("app", "replica", named_db("replica")),
+        ]);
+
+        assert_eq!(resolve(&fixtures, db(), "app"), Ok("app::primary".into()));
+        assert_eq!(
+            resolve(&fixtures, named_db("replica"), "app"),
+            Ok("app::replica".into())
+        );
+        assert_eq!(
+            resolve(&fixtures, named_db("backup"), "app"),
+            Err(format!(
+                "`app::test` requires `{}` named \"backup\" but no fixture provides it",
+                db().type_name
+            ))
+        );
+    }
+
+    #[test]
+    fn unnamed_request_falls_back_to_named_providers() {
+        let one = fixtures(vec![FixtureConfig::stub(
+            "app",
+            "replica",
+            named_db("replica"),
+        )]);
+        assert_eq!(resolve(&one, db(), "app"), Ok("app::replica".into()));
+
+        let two = fixtures(vec![
+            FixtureConfig::stub("app", "replica", named_db("replica")),
+            FixtureConfig::stub("app", "backup", named_db("backup")),
+        ]);
+        assert!(resolve(&two, db(), "app")
+            .unwrap_err()
+            .contains("ambiguous because it is provided by `app::backup`, `app::replica`"));
+    }
+
+    fn module_override(module_path: &str, name: &str) -> FixtureConfig {
+        FixtureConfig {
+            module_override: true,
+            ..FixtureConfig::stub(module_path, name, db())
+        }
+    }
+
+    #[test]
+    fn override_applies_to_its_module_subtree() {
+        let fixtures = fixtures(vec![
+            FixtureConfig::stub("app", "db", db()),
+            module_override("app::net", "failing_db"),
+        ]);
+
+        assert_eq!(resolve(&fixtures, db(), "app"), Ok("app::db".into()));
+        assert_eq!(
+            resolve(&fixtures, db(), "app::net"),
+            Ok("app::net::failing_db".into())
+        );
+        assert_eq!(
+            resolve(&fixtures, db(), "app::net::retry"),
+            Ok("app::net::failing_db".into())
+        );
+        // A module that merely starts with the same name isn't in the subtree
+        assert_eq!(
+            resolve(&fixtures, db(), "app::network"),
+            Ok("app::db".into())
+        );
+    }
+
+    #[test]
+    fn closest_override_wins() {
+        let fixtures = fixtures(vec![
+            FixtureConfig::stub("app", "db", db()),
+            module_override("app", "local_db"),
+            module_override("app::net", "failing_db"),
+        ]);
+
+        assert_eq!(resolve(&fixtures, db(), "app"), Ok("app::local_db".into()));
+        assert_eq!(
+            resolve(&fixtures, db(), "app::net::retry"),
+            Ok("app::net::failing_db".into())
+        );
+    }
+
+    #[test]
+    fn profile_provider_replaces_default_provider() {
+        let ci_db = FixtureConfig {
+            profile: Some("ci".to_string()),
+            ..FixtureConfig::stub("app", "postgres", db())
+        };
+        let fixtures = fixtures(vec![FixtureConfig::stub("app", "in_memory", db()), ci_db]);
+
+        assert_eq!(resolve(&fixtures, db(), "app"), Ok("app::postgres".into()));
+    }
+
+    #[test]
+    fn profile_provider_replaces_override_of_the_same_module() {
+        let ci_db = FixtureConfig {
+            profile: Some("ci".to_string()),
+            ..module_override("app::net", "ci_failing_db")
+        };
+        let fixtures = fixtures(vec![
+            FixtureConfig::stub("app", "db", db()),
+            module_override("app::net", "failing_db"),
+            ci_db,
+        ]);
+
+        assert_eq!(resolve(&fixtures, db(), "app"), Ok("app::db".into()));
+        assert_eq!(
+            resolve(&fixtures, db(), "app::net"),
+            Ok("app::net::ci_failing_db".into())
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use std::{
-    any::{Any, TypeId},
-    collections::HashMap,
+    any::Any,
     error::Error,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
 };
 
 use crate::codegen::config::InputRef;
@@ -20,23 +22,92 @@ pub type RunnableFn = &'static dyn Fn(RunnableInput) -> Result<(), Box<dyn Error
 /// Receives output from a runnable
 #[derive(Default)]
 pub struct Receiver {
-    pub(crate) outputs: HashMap<TypeId, Box<dyn Any>>,
-    // TODO: expecting: HashSet<TypeId> ? that way assert!(expecting.is_empty()) at end
+    pub(crate) output: Option<Box<dyn Any>>,
+    /// Counts the references to the `Arc` of the output, which can't be done once its type is
+    /// erased
+    pub(crate) strong_count: Option<fn(&dyn Any) -> usize>,
 }
 
 impl Receiver {
-    pub fn receive_output<T: 'static>(&mut self, output: T) {
-        self.outputs
-            .insert(TypeId::of::<T>(), Box::new(output) as Box<dyn Any>);
+    /// Receives the output of a fixture, which is stored behind an `Arc` so that tests can share it
+    /// beyond their own run.
+    pub fn receive_output<T: ?Sized + 'static>(&mut self, output: Arc<T>) {
+        self.output = Some(Box::new(output) as Box<dyn Any>);
+        self.strong_count = Some(strong_count::<T>);
     }
 }
 
+fn strong_count<T: ?Sized + 'static>(output: &dyn Any) -> usize {
+    let output: &Arc<T> = output
+        .downcast_ref()
+        .expect("failed to downcast into inner type");
+
+    Arc::strong_count(output)
+}
+
+/// Runs the producer of a fixture on its own, giving a fresh instance (stored in an `Arc` like
+/// every fixture output) to a single consumer instead of the instance shared with other tests.
+///
+/// Producers take no inputs, so they are given no dependencies.
+pub(crate) fn produce(producer: RunnableFn) -> Result<Box<dyn Any>, Box<dyn Error>> {
+    let mut receiver = Receiver::default();
+    let input = RunnableInput {
+        dependencies: Dependencies::new(),
+        receiver: &mut receiver,
+    };
+    catch_panic(|| producer(input))?;
+
+    Ok(receiver.output.expect("producers always have an output"))
+}
+
+/// Takes the instance of `T` out of the output of [`produce`].
+pub(crate) fn into_instance<T: 'static>(output: Box<dyn Any>) -> T {
+    let output: Box<Arc<T>> = output
+        .downcast()
+        .expect("failed to downcast into inner type");
+
+    Arc::try_unwrap(*output)
+        .ok()
+        .expect("a fresh instance is only referenced by its consumer")
+}
+
 /// The struct given to a runnable
 pub struct RunnableInput<'dep, 'recv> {
     pub dependencies: Dependencies<'dep>,
     pub receiver: &'recv mut Receiver,
 }
 
+/// The error a runnable fails with when it panics
+#[derive(Debug)]
+pub struct PanicError {
+    pub message: String,
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "panicked: {}", self.message)
+    }
+}
+
+impl Error for PanicError {}
+
+/// Runs `f` turning any panic into a [`PanicError`].
+pub(crate) fn catch_panic(
+    f: impl FnOnce() -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+
+        Err(Box::new(PanicError { message }))
+    })
+}
+
 pub struct BasicRunnable {
     pub inputs: Vec<InputRef>,
     pub runner: RunnableFn,
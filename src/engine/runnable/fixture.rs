@@ -1,6 +1,6 @@
 use crate::codegen::config::InputRef;
 
-use super::{BasicRunnable, Runnable};
+use super::{catch_panic, BasicRunnable, Runnable};
 
 pub struct FixtureRunnable {
     pub runnable: BasicRunnable,
@@ -10,7 +10,7 @@ pub struct FixtureRunnable {
 
 impl Runnable for FixtureRunnable {
     fn run(&self, input: super::RunnableInput) -> Result<(), Box<dyn std::error::Error>> {
-        self.runnable.run(input)
+        catch_panic(|| self.runnable.run(input))
     }
 
     fn inputs(&self) -> &[InputRef] {
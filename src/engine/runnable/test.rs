@@ -1,6 +1,6 @@
 use crate::codegen::config::InputRef;
 
-use super::{BasicRunnable, Runnable};
+use super::{catch_panic, BasicRunnable, PanicError, Runnable};
 
 pub struct TestRunnable {
     pub should_panic: bool,
@@ -12,9 +12,18 @@ pub struct TestRunnable {
 
 impl Runnable for TestRunnable {
     fn run(&self, input: super::RunnableInput) -> Result<(), Box<dyn std::error::Error>> {
-        // TODO: panic handling, timeouts, etc...
+        // TODO: timeouts, etc...
+        let result = catch_panic(|| self.basic_runnable.run(input));
 
-        self.basic_runnable.run(input)
+        if !self.should_panic {
+            return result;
+        }
+
+        match result {
+            Err(err) if err.is::<PanicError>() => Ok(()),
+            Err(err) => Err(err),
+            Ok(()) => Err("the test did not panic as expected".into()),
+        }
     }
 
     fn inputs(&self) -> &[InputRef] {
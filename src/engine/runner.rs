@@ -1,32 +1,54 @@
+mod graph;
 mod schedule;
 
 use self::schedule::START_NODE;
 
 use super::{
+    capture,
     dependencies::Dependencies,
-    runnable::{Runnable, RunnableInput},
+    records,
+    runnable::{self, Runnable, RunnableFn, RunnableInput},
     EngineConfig, Label,
 };
 use crate::{
     codegen::config::InputRef,
     engine::runnable::{BasicRunnable, FixtureRunnable, TestRunnable},
+    options::GraphFormat,
+    report::{
+        Event, FixtureInfo, GroupInfo, Outcome, Output, Plan, Reporter, RunInfo, Summary, TestInfo,
+    },
 };
 use parking_lot::RwLock;
-pub use schedule::Schedule;
 use schedule::ScheduleBuilder;
+pub use schedule::{Schedule, Transition};
 use std::{
-    any::{Any, TypeId},
-    collections::HashMap,
-    ops::Deref,
+    any::Any,
+    collections::{HashMap, HashSet},
+    time::Instant,
 };
 use uuid::Uuid;
 
 pub struct Runner {
     schedule: Schedule,
     runnables: HashMap<Uuid, Box<dyn Runnable>>,
+    /// The producers (and names) of the fixtures that tests take by value or a `Factory` of
+    producers: HashMap<Uuid, (String, RunnableFn)>,
+    /// Everything that will run, reported at the start of the run
+    plan: Plan,
+    /// The position of each test in the plan
+    test_index: HashMap<Uuid, usize>,
+    /// The position of each fixture in the plan
+    fixture_index: HashMap<Uuid, usize>,
+    /// The fixtures that failed (and so have no output) along with why
+    failed_fixtures: HashMap<Uuid, String>,
+    /// The fixture nodes that provide each input of a runnable (in the same order as the inputs),
+    /// `None` for an optional input that no fixture provides
+    resolved_inputs: HashMap<Uuid, Vec<Option<Uuid>>>,
     // TODO: think of better name and then define trait instead of using Any and make sure it has
     // Send
-    outputs: HashMap<TypeId, RwLock<Box<dyn Any>>>,
+    outputs: HashMap<Uuid, RwLock<Box<dyn Any>>>,
+    /// Counts the references to the `Arc` each output is stored in
+    strong_counts: HashMap<Uuid, fn(&dyn Any) -> usize>,
 }
 
 // TODO: eventually once tests can also output data, try to abstract away what is a test and what
@@ -36,16 +58,15 @@ impl Runner {
     pub fn new(config: &EngineConfig) -> Runner {
         let mut builder = ScheduleBuilder::new();
         let mut runnables = HashMap::new();
-        let mut fixture_nodes: HashMap<TypeId, Uuid> = HashMap::new();
+        let mut producers = HashMap::new();
+        let mut fixture_nodes = HashSet::new();
+        let mut plan = Plan::default();
 
         for (test_id, test_config) in &config.tests {
             builder.register_node(*test_id);
             if let Some(excludes_with) = &test_config.exclusion_group {
-                for other in config
-                    .labels
-                    .get(&Label::User(excludes_with.to_string()))
-                    .unwrap()
-                {
+                // The test itself is always in the group
+                for other in &config.labels[&Label::User(excludes_with.to_string())] {
                     if test_id != other {
                         builder.add_exclusion(*test_id, *other);
                     }
@@ -64,17 +85,53 @@ impl Runner {
                 }) as Box<dyn Runnable>,
             );
 
-            for input in &test_config.inputs {
+            let resolved_inputs = &config.resolved_inputs[test_id];
+            plan.tests.push(TestInfo {
+                id: *test_id,
+                module_path: test_config.module_path.clone(),
+                name: test_config.name.clone(),
+                fixtures: test_config
+                    .inputs
+                    .iter()
+                    .zip(resolved_inputs)
+                    .filter(|(input, _)| {
+                        !matches!(input.required(), InputRef::Owned(_) | InputRef::Factory(_))
+                    })
+                    .filter_map(|(_, fixture_id)| *fixture_id)
+                    .collect(),
+            });
+
+            for (input, input_uuid) in test_config.inputs.iter().zip(resolved_inputs) {
                 use crate::codegen::config::InputRef::*;
 
-                let input_uuid = *fixture_nodes.entry(input.id()).or_insert_with(|| {
-                    let input_uuid = Uuid::new_v4();
-                    let fixture_config = config
-                        .fixtures
-                        .get(&dbg!(input.id()))
-                        .expect("input is not a fixture");
+                // An optional input that no fixture provides
+                let Some(input_uuid) = input_uuid else {
+                    continue;
+                };
+
+                // Owned inputs and factories get fresh instances from the producer of the fixture
+                // (which takes no inputs, so it can run on its own)
+                if let Owned(_) | Factory(_) = input.required() {
+                    let fixture_config = &config.fixtures[input_uuid];
+                    assert!(
+                        fixture_config.inputs.is_empty(),
+                        "producers can't take inputs"
+                    );
+                    producers.insert(
+                        *input_uuid,
+                        (fixture_config.fqn(), fixture_config.runnable_fn),
+                    );
+
+                    // The producer runs as part of the test (or whenever the factory is called)
+                    // instead of as a node, so a failing producer fails the test and its output
+                    // is captured with the output of the test
+                    continue;
+                }
+
+                if fixture_nodes.insert(*input_uuid) {
+                    let fixture_config = &config.fixtures[input_uuid];
                     runnables.insert(
-                        input_uuid,
+                        *input_uuid,
                         Box::new(FixtureRunnable {
                             runnable: BasicRunnable {
                                 inputs: vec![],
@@ -82,97 +139,519 @@ impl Runner {
                             },
                         }),
                     );
-                    builder.register_node(*test_id);
-
-                    input_uuid
-                });
+                    builder.register_node(*input_uuid);
+                    plan.fixtures.push(FixtureInfo {
+                        id: *input_uuid,
+                        module_path: fixture_config.module_path.clone(),
+                        name: fixture_config.name.clone(),
+                        type_name: fixture_config.output.type_name,
+                    });
+                }
 
-                match *input {
-                    Shared(_) => {
-                        builder.add_dependency(input_uuid, *test_id);
+                match input.required() {
+                    Shared(_) | SharedArc(_) => {
+                        builder.add_dependency(*input_uuid, *test_id);
                     }
                     Exclusive(_) => {
-                        builder.add_dependency(input_uuid, *test_id);
-                        for other in config.labels.get(&Label::Dependency(input.id())).unwrap() {
-                            if other != &input_uuid {
-                                builder.add_exclusion(*other, input_uuid);
+                        builder.add_dependency(*input_uuid, *test_id);
+                        for other in &config.labels[&Label::Dependency(*input_uuid)] {
+                            if other != test_id {
+                                builder.add_exclusion(*other, *test_id);
                             }
                         }
                     }
-                    Owned(_) => {}
+                    Owned(_) | Factory(_) => unreachable!("producers aren't nodes"),
+                    Optional(_) => unreachable!("`required` unwraps optional inputs"),
                 }
             }
         }
 
+        plan.tests.sort_by_key(TestInfo::fqn);
+        plan.fixtures.sort_by_key(FixtureInfo::fqn);
+        let test_index: HashMap<_, _> = plan
+            .tests
+            .iter()
+            .enumerate()
+            .map(|(i, test)| (test.id, i))
+            .collect();
+        let fixture_index = plan
+            .fixtures
+            .iter()
+            .enumerate()
+            .map(|(i, fixture)| (fixture.id, i))
+            .collect();
+
+        plan.groups = config
+            .groups
+            .values()
+            .map(|group| {
+                let mut tests = group.tests.clone();
+                tests.sort_by_key(|id| test_index[id]);
+
+                GroupInfo {
+                    name: group.name.clone(),
+                    tests,
+                }
+            })
+            .collect();
+        plan.groups.sort_by(|a, b| a.name.cmp(&b.name));
+
         Runner {
             schedule: builder.build(),
             runnables,
+            producers,
+            plan,
+            test_index,
+            fixture_index,
+            failed_fixtures: HashMap::new(),
+            resolved_inputs: config.resolved_inputs.clone(),
             outputs: Default::default(),
+            strong_counts: Default::default(),
         }
     }
 
-    pub fn run(&mut self) {
+    pub fn plan(&self) -> &Plan {
+        &self.plan
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// Steps through the schedule without running anything, as if there were as many threads as
+    /// needed. Each wave is every node that could run at the same time once the waves before it
+    /// have finished.
+    ///
+    /// This uses up the schedule so the runner can't run afterwards.
+    pub fn waves(&mut self) -> Vec<Vec<Uuid>> {
+        let mut waves = Vec::new();
+
+        loop {
+            let mut wave = Vec::new();
+            while let schedule::NextInSchedule::Next(id) = self.schedule.next() {
+                wave.push(id);
+            }
+            if wave.is_empty() {
+                break;
+            }
+
+            for id in &wave {
+                self.schedule.complete_node(*id);
+            }
+            self.schedule.take_transitions();
+
+            wave.retain(|id| *id != START_NODE);
+            if !wave.is_empty() {
+                waves.push(wave);
+            }
+        }
+
+        waves
+    }
+
+    /// Renders the schedule as a graph in `format`.
+    pub fn graph(&self, format: GraphFormat) -> String {
+        graph::render(&self.plan, &self.schedule, format)
+    }
+
+    /// Runs every node in the schedule, reporting the progress to `reporter`.
+    pub fn run(&mut self, run: &RunInfo, reporter: &mut dyn Reporter) -> Summary {
+        let start = Instant::now();
+        let mut summary = Summary::default();
+        reporter.report(&Event::RunStarted {
+            run,
+            plan: &self.plan,
+        });
+
         loop {
             match self.schedule.next() {
                 schedule::NextInSchedule::Running => unreachable!("currently single threaded"),
-                schedule::NextInSchedule::Completed => return,
+                schedule::NextInSchedule::Completed => break,
                 schedule::NextInSchedule::Next(id) => {
-                    if let Some(runnable) = self.runnables.get(&id) {
-                        // let mut shared_guards = Vec::new();
-                        // let mut exclusive_guards = Vec::new();
-
-                        // TODO: sub node tainting...
-                        let mut dependencies = Dependencies::new();
-                        for input in runnable.inputs() {
-                            use InputRef::*;
-                            match input {
-                                Shared(id) => {
-                                    let guard = self
-                                        .outputs
-                                        .get(id)
-                                        .unwrap()
-                                        .try_read()
-                                        .expect("mutual exclusion prevents locks");
-                                    // shared_guards.push(guard);
-                                    dependencies.add_shared(*id, guard);
-                                }
-                                Exclusive(id) => {
-                                    let guard = self
-                                        .outputs
-                                        .get(id)
-                                        .unwrap()
-                                        .try_write()
-                                        .expect("mutual exclusion prevents locks");
-                                    // exclusive_guards.push(guard);
-                                    dependencies.add_exclusive(*id, guard);
-                                }
-                                _ => todo!(),
-                            }
-                        }
+                    if self.runnables.contains_key(&id) {
+                        self.run_node(id, run.capture, reporter, &mut summary);
+                        self.schedule.complete_node(id);
+                    } else if id == START_NODE {
+                        self.schedule.complete_node(id);
+                    } else {
+                        panic!("unknown ID: {}", id);
+                    }
+
+                    for (id, transition) in self.schedule.take_transitions() {
+                        reporter.report(&Event::Scheduled {
+                            id,
+                            transition: &transition,
+                        });
+                    }
+                }
+            }
+        }
+
+        summary.duration = start.elapsed();
+        reporter.report(&Event::RunFinished { summary: &summary });
+
+        summary
+    }
+
+    /// The outcome of a runnable whose inputs take `fixture_id` in ways that can't be combined (e.g.
+    /// `&T` and `&mut T`), which validation rejects before anything runs.
+    fn conflicting_access(&self, fixture_id: &Uuid) -> Outcome {
+        Outcome::Errored {
+            message: format!(
+                "fixture `{}` is taken as `&mut` together with another borrow of it",
+                self.plan.fixtures[self.fixture_index[fixture_id]].fqn(),
+            ),
+        }
+    }
+
+    fn run_node(
+        &mut self,
+        id: Uuid,
+        capture: bool,
+        reporter: &mut dyn Reporter,
+        summary: &mut Summary,
+    ) {
+        let test = self.test_index.get(&id).map(|i| &self.plan.tests[*i]);
+        let fixture = self.fixture_index.get(&id).map(|i| &self.plan.fixtures[*i]);
+
+        match (test, fixture) {
+            (Some(test), _) => reporter.report(&Event::TestStarted { test, worker: 0 }),
+            (_, Some(fixture)) => reporter.report(&Event::FixtureStarted { fixture, worker: 0 }),
+            _ => unreachable!("every runnable is either a test or a fixture"),
+        }
+
+        let resolved_inputs = self
+            .resolved_inputs
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let start = Instant::now();
+        let mut output = Output::default();
+        let runnable = &self.runnables[&id];
+        let failed_fixture = runnable
+            .inputs()
+            .iter()
+            .zip(resolved_inputs)
+            // Owned inputs and factories run the producer instead of using the fixture node
+            .filter(|(input, _)| {
+                !matches!(input.required(), InputRef::Owned(_) | InputRef::Factory(_))
+            })
+            .find_map(|(_, fixture_id)| {
+                fixture_id
+                    .as_ref()
+                    .filter(|id| self.failed_fixtures.contains_key(id))
+            });
+
+        let outcome = 'outcome: {
+            if let Some(failed_fixture) = failed_fixture {
+                break 'outcome Outcome::Errored {
+                    message: format!(
+                        "fixture `{}` failed: {}",
+                        self.plan.fixtures[self.fixture_index[failed_fixture]].fqn(),
+                        self.failed_fixtures[failed_fixture]
+                    ),
+                };
+            }
 
-                        let mut receiver = Default::default();
+            // let mut shared_guards = Vec::new();
+            // let mut exclusive_guards = Vec::new();
 
-                        let input = RunnableInput {
-                            dependencies,
-                            receiver: &mut receiver,
+            // TODO: sub node tainting...
+            let mut dependencies = Dependencies::new();
+            let mut owned = Vec::new();
+            for (input, fixture_id) in runnable.inputs().iter().zip(resolved_inputs) {
+                // Optional inputs that no fixture provides are left out of the dependencies
+                let Some(fixture_id) = fixture_id else {
+                    continue;
+                };
+
+                use InputRef::*;
+                match input.required() {
+                    // Mutual exclusion keeps other runnables from holding the locks, so a lock
+                    // can only be taken if another input of this runnable holds it
+                    Shared(fixture) | SharedArc(fixture) => {
+                        let Some(guard) = self.outputs.get(fixture_id).unwrap().try_read() else {
+                            break 'outcome self.conflicting_access(fixture_id);
+                        };
+                        // shared_guards.push(guard);
+                        dependencies.add_shared(*fixture, guard);
+                    }
+                    Exclusive(fixture) => {
+                        let Some(guard) = self.outputs.get(fixture_id).unwrap().try_write() else {
+                            break 'outcome self.conflicting_access(fixture_id);
                         };
-                        runnable.run(input).expect("test failed");
+                        // A test that took the fixture as an `Arc` can keep it (e.g. in a thread it
+                        // spawned) after it finishes, in which case it can't be borrowed mutably
+                        if self.strong_counts[fixture_id](&**guard) > 1 {
+                            break 'outcome Outcome::Errored {
+                                message: format!(
+                                    "fixture `{}` is taken as `&mut` but another test still keeps \
+                                     it in an `Arc`",
+                                    self.plan.fixtures[self.fixture_index[fixture_id]].fqn(),
+                                ),
+                            };
+                        }
+                        // exclusive_guards.push(guard);
+                        dependencies.add_exclusive(*fixture, guard);
+                    }
+                    // Produced when the test runs, see `run` below
+                    Owned(fixture) => owned.push((*fixture, &self.producers[fixture_id])),
+                    Factory(fixture) => {
+                        dependencies.add_factory(*fixture, self.producers[fixture_id].1);
+                    }
+                    Optional(_) => unreachable!("`required` unwraps optional inputs"),
+                }
+            }
 
-                        self.outputs.extend(
+            let mut receiver = Default::default();
+
+            // Owned inputs are produced as part of the test, so the output and records of their
+            // producers belong to the test and a failing producer fails the test
+            let run = || {
+                for (fixture, (name, producer)) in owned {
+                    let instance = runnable::produce(*producer)
+                        .map_err(|err| format!("fixture `{name}` failed: {err}"))?;
+                    dependencies.add_owned(fixture, instance);
+                }
+
+                runnable.run(RunnableInput {
+                    dependencies,
+                    receiver: &mut receiver,
+                })
+            };
+
+            let (result, captured) = if capture {
+                let ((result, records), mut captured) = capture::capture(|| records::collect(run));
+                captured.records = records;
+                (result, captured)
+            } else {
+                (run(), Output::default())
+            };
+            output = captured;
+
+            match result {
+                Ok(()) => {
+                    if let Some(output) = receiver.output {
+                        self.outputs.insert(id, RwLock::new(output));
+                        self.strong_counts.insert(
+                            id,
                             receiver
-                                .outputs
-                                .into_iter()
-                                .map(|(key, value)| (key, RwLock::new(value))),
+                                .strong_count
+                                .expect("outputs are received with a count"),
                         );
-                        self.schedule.complete_node(id);
-                    } else if id == START_NODE {
-                        self.schedule.complete_node(id);
-                        continue;
-                    } else {
-                        panic!("unknown ID: {}", id);
                     }
+                    Outcome::Passed
                 }
+                Err(err) => Outcome::Failed {
+                    message: err.to_string(),
+                },
+            }
+        };
+        let duration = start.elapsed();
+
+        if let Some(test) = test {
+            match outcome {
+                Outcome::Passed => summary.passed += 1,
+                Outcome::Failed { .. } => summary.failed += 1,
+                Outcome::Errored { .. } => summary.errored += 1,
+            }
+
+            reporter.report(&Event::TestFinished {
+                test,
+                outcome: &outcome,
+                duration,
+                output: &output,
+            });
+        } else if let Some(fixture) = fixture {
+            if let Outcome::Failed { message } = &outcome {
+                self.failed_fixtures.insert(id, message.clone());
             }
+
+            reporter.report(&Event::FixtureFinished {
+                fixture,
+                outcome: &outcome,
+                duration,
+                output: &output,
+            });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, sync::Arc};
+
+    use crate::{
+        codegen::config::{
+            testing::{db, Db},
+            FixtureConfig, InputRef, TestConfig,
+        },
+        engine::Engine,
+        options::Options,
+        report::{Event, Outcome, Reporter},
+    };
+
+    fn engine(tests: &[fn() -> TestConfig]) -> Engine {
+        let fixtures: &[fn() -> FixtureConfig] = &[|| FixtureConfig::stub("app", "db", db())];
+        Engine::new(tests, fixtures, &Options::default()).unwrap()
+    }
+
+    /// The names of the nodes in each pair, sorted
+    fn names(engine: &Engine, pairs: Vec<(uuid::Uuid, uuid::Uuid)>) -> BTreeSet<(String, String)> {
+        let name = |id| match engine.config.tests.get(&id) {
+            Some(test) => test.name.clone(),
+            None => engine.config.fixtures[&id].name.clone(),
+        };
+
+        pairs
+            .into_iter()
+            .map(|(a, b)| {
+                let (a, b) = (name(a), name(b));
+                if a < b {
+                    (a, b)
+                } else {
+                    (b, a)
+                }
+            })
+            .collect()
+    }
+
+    fn pairs<const N: usize>(pairs: [(&str, &str); N]) -> BTreeSet<(String, String)> {
+        pairs
+            .into_iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    }
+
+    /// An exclusive consumer excludes the other consumers of the fixture (it used to make them
+    /// exclude the fixture node instead, which let it run alongside them).
+    #[test]
+    fn exclusive_consumer_excludes_other_consumers() {
+        let engine = engine(&[
+            || TestConfig::stub("app", "writes", vec![InputRef::exclusive(db())]),
+            || TestConfig::stub("app", "reads", vec![InputRef::shared(db())]),
+            || TestConfig::stub("app", "reads_too", vec![InputRef::shared(db())]),
+        ]);
+
+        assert_eq!(
+            names(&engine, engine.runner.schedule().exclusions()),
+            pairs([("reads", "writes"), ("reads_too", "writes")])
+        );
+    }
+
+    /// The fixture node is registered with the schedule and runs before its consumers. It used to
+    /// register the test a second time instead, so a fixture without a dependency edge never ran.
+    /// Owned inputs run the producer as part of the test, so they don't depend on the node.
+    #[test]
+    fn fixture_runs_before_consumers() {
+        let mut engine = engine(&[
+            || TestConfig::stub("app", "reads", vec![InputRef::shared(db())]),
+            || TestConfig::stub("app", "owns", vec![InputRef::owned(db())]),
+        ]);
+
+        assert_eq!(
+            names(&engine, engine.runner.schedule().dependencies()),
+            pairs([("db", "reads")])
+        );
+
+        let waves = engine.runner.waves();
+        assert_eq!(waves.len(), 2);
+        assert!(waves[0]
+            .iter()
+            .any(|id| engine.config.fixtures.contains_key(id)));
+        assert_eq!(engine.config.tests[&waves[1][0]].name, "reads");
+    }
+
+    /// Only owned inputs use a fixture, so no fixture node runs.
+    #[test]
+    fn owned_inputs_have_no_fixture_node() {
+        let mut engine = engine(&[|| TestConfig::stub("app", "owns", vec![InputRef::owned(db())])]);
+
+        assert_eq!(engine.runner.waves().concat().len(), 1);
+    }
+
+    /// Collects the outcome of each test.
+    #[derive(Default)]
+    struct Outcomes(Vec<(String, Outcome)>);
+
+    impl Reporter for Outcomes {
+        fn report(&mut self, event: &Event<'_>) {
+            if let Event::TestFinished { test, outcome, .. } = event {
+                self.0.push((test.name.clone(), (*outcome).clone()));
+            }
+        }
+    }
+
+    /// A fixture that is still kept in an `Arc` (e.g. by a thread a test spawned) only fails the
+    /// tests taking it as `&mut`.
+    #[test]
+    fn fixture_kept_in_arc_fails_exclusive_consumer() {
+        let tests: &[fn() -> TestConfig] = &[
+            || TestConfig::stub("app", "reads", vec![InputRef::shared(db())]),
+            || TestConfig::stub("app", "writes", vec![InputRef::exclusive(db())]),
+        ];
+        let fixtures: &[fn() -> FixtureConfig] = &[|| FixtureConfig {
+            runnable_fn: &|input| {
+                let db = Arc::new(Db);
+                std::mem::forget(Arc::clone(&db));
+                input.receiver.receive_output(db);
+                Ok(())
+            },
+            ..FixtureConfig::stub("app", "db", db())
+        }];
+        let options = Options {
+            nocapture: true,
+            ..Options::default()
+        };
+        let mut outcomes = Outcomes::default();
+        Engine::new(tests, fixtures, &options)
+            .unwrap()
+            .run(&mut outcomes);
+        outcomes.0.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            outcomes.0,
+            [
+                ("reads".to_string(), Outcome::Passed),
+                (
+                    "writes".to_string(),
+                    Outcome::Errored {
+                        message: "fixture `app::db` is taken as `&mut` but another test still \
+                                  keeps it in an `Arc`"
+                            .to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    /// A producer that panics while producing an owned input fails the test taking it.
+    #[test]
+    fn failing_producer_fails_owned_consumer() {
+        let tests: &[fn() -> TestConfig] =
+            &[|| TestConfig::stub("app", "owns", vec![InputRef::owned(db())])];
+        let fixtures: &[fn() -> FixtureConfig] = &[|| FixtureConfig {
+            runnable_fn: &|_| panic!("no database"),
+            ..FixtureConfig::stub("app", "db", db())
+        }];
+        let options = Options {
+            nocapture: true,
+            ..Options::default()
+        };
+        let mut outcomes = Outcomes::default();
+        Engine::new(tests, fixtures, &options)
+            .unwrap()
+            .run(&mut outcomes);
+
+        assert_eq!(
+            outcomes.0,
+            [(
+                "owns".to_string(),
+                Outcome::Failed {
+                    message: "fixture `app::db` failed: panicked: no database".to_string()
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Renders the schedule as a graph (`--graph`), so it's clear at a glance what waits on what.
+
+use std::{collections::HashMap, fmt::Write};
+
+use uuid::Uuid;
+
+use super::Schedule;
+use crate::{options::GraphFormat, report::Plan};
+
+/// A node of the graph
+struct Node {
+    name: String,
+    fixture: bool,
+}
+
+/// Renders the dependencies (solid arrows from a fixture to the tests using it) and mutual
+/// exclusions (dashed lines between the nodes that can't run at the same time) of `schedule`.
+pub fn render(plan: &Plan, schedule: &Schedule, format: GraphFormat) -> String {
+    let nodes: Vec<_> = plan
+        .fixtures
+        .iter()
+        .map(|fixture| {
+            (
+                fixture.id,
+                Node {
+                    name: fixture.fqn(),
+                    fixture: true,
+                },
+            )
+        })
+        .chain(plan.tests.iter().map(|test| {
+            (
+                test.id,
+                Node {
+                    name: test.fqn(),
+                    fixture: false,
+                },
+            )
+        }))
+        .collect();
+    let index: HashMap<Uuid, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| (*id, i))
+        .collect();
+
+    let edges = |edges: Vec<(Uuid, Uuid)>, directed: bool| {
+        let mut edges: Vec<_> = edges
+            .into_iter()
+            .map(|(a, b)| (index[&a], index[&b]))
+            .map(|(a, b)| {
+                if directed {
+                    (a, b)
+                } else {
+                    (a.min(b), a.max(b))
+                }
+            })
+            .collect();
+        edges.sort_unstable();
+        edges
+    };
+    let dependencies = edges(schedule.dependencies(), true);
+    let exclusions = edges(schedule.exclusions(), false);
+
+    let nodes: Vec<_> = nodes.into_iter().map(|(_, node)| node).collect();
+    match format {
+        GraphFormat::Dot => dot(&nodes, &dependencies, &exclusions),
+        GraphFormat::Mermaid => mermaid(&nodes, &dependencies, &exclusions),
+    }
+}
+
+fn dot(nodes: &[Node], dependencies: &[(usize, usize)], exclusions: &[(usize, usize)]) -> String {
+    let escape = |name: &str| name.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut dot = String::from("digraph critic {\n    rankdir=LR;\n");
+    for (i, node) in nodes.iter().enumerate() {
+        let shape = if node.fixture { "box" } else { "ellipse" };
+        let _ = writeln!(
+            dot,
+            "    n{i} [label=\"{}\", shape={shape}];",
+            escape(&node.name)
+        );
+    }
+    for (parent, child) in dependencies {
+        let _ = writeln!(dot, "    n{parent} -> n{child};");
+    }
+    for (a, b) in exclusions {
+        let _ = writeln!(
+            dot,
+            "    n{a} -> n{b} [dir=none, style=dashed, color=red, constraint=false];"
+        );
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+fn mermaid(
+    nodes: &[Node],
+    dependencies: &[(usize, usize)],
+    exclusions: &[(usize, usize)],
+) -> String {
+    let escape = |name: &str| {
+        name.replace('"', "#quot;")
+            .replace('<', "#lt;")
+            .replace('>', "#gt;")
+    };
+
+    let mut mermaid = String::from("flowchart LR\n");
+    for (i, node) in nodes.iter().enumerate() {
+        let name = escape(&node.name);
+        if node.fixture {
+            let _ = writeln!(mermaid, "    n{i}[(\"{name}\")]");
+        } else {
+            let _ = writeln!(mermaid, "    n{i}([\"{name}\"])");
+        }
+    }
+    for (parent, child) in dependencies {
+        let _ = writeln!(mermaid, "    n{parent} --> n{child}");
+    }
+    for (a, b) in exclusions {
+        let _ = writeln!(mermaid, "    n{a} -. excludes .- n{b}");
+    }
+
+    mermaid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        codegen::config::{testing::db, FixtureConfig, InputRef, TestConfig},
+        engine::Engine,
+        options::Options,
+    };
+
+    /// `app::db` (`n0`) is read by `app::reads` (`n1`) and written by the test with the name that
+    /// needs escaping (`n2`), which excludes the reader.
+    fn graph(format: GraphFormat) -> String {
+        let tests: &[fn() -> TestConfig] = &[
+            || TestConfig::stub("app", "reads", vec![InputRef::shared(db())]),
+            || TestConfig::stub("app", r#"writes::<"a\b">"#, vec![InputRef::exclusive(db())]),
+        ];
+        let fixtures: &[fn() -> FixtureConfig] = &[|| FixtureConfig::stub("app", "db", db())];
+        let engine = Engine::new(tests, fixtures, &Options::default()).unwrap();
+
+        render(engine.runner.plan(), engine.runner.schedule(), format)
+    }
+
+    #[test]
+    fn renders_dot() {
+        assert_eq!(
+            graph(GraphFormat::Dot),
+            "digraph critic {\n    rankdir=LR;\n    \
+             n0 [label=\"app::db\", shape=box];\n    \
+             n1 [label=\"app::reads\", shape=ellipse];\n    \
+             n2 [label=\"app::writes::<\\\"a\\\\b\\\">\", shape=ellipse];\n    \
+             n0 -> n1;\n    \
+             n0 -> n2;\n    \
+             n1 -> n2 [dir=none, style=dashed, color=red, constraint=false];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn renders_mermaid() {
+        assert_eq!(
+            graph(GraphFormat::Mermaid),
+            "flowchart LR\n    \
+             n0[(\"app::db\")]\n    \
+             n1([\"app::reads\"])\n    \
+             n2([\"app::writes::#lt;#quot;a\\b#quot;#gt;\"])\n    \
+             n0 --> n1\n    \
+             n0 --> n2\n    \
+             n1 -. excludes .- n2\n"
+        );
+    }
+}
@@ -24,6 +24,17 @@ pub struct Schedule {
     /// The queue of nodes that are able to run (no dependency/exclusion).
     /// Any nodes is here count as part of mutual exclusion rules of running nodes.
     queue: VecDeque<Uuid>,
+    /// The changes in the state of nodes since they were last taken
+    transitions: Vec<(Uuid, Transition)>,
+}
+
+/// A change in the state of a node
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transition {
+    /// The dependencies of the node completed but it is mutually excluded by these active nodes
+    Excluded { by: Vec<Uuid> },
+    /// The node is queued to run
+    Ready,
 }
 
 #[derive(Clone, Debug)]
@@ -55,6 +66,34 @@ impl Schedule {
         NextInSchedule::Running
     }
 
+    /// Every dependency as `(parent, child)` (where the child depends on the parent), leaving out
+    /// the start node.
+    pub fn dependencies(&self) -> Vec<(Uuid, Uuid)> {
+        self.nodes
+            .iter()
+            .filter(|(id, _)| **id != START_NODE)
+            .flat_map(|(parent, node)| node.dependents.iter().map(|child| (*parent, *child)))
+            .collect()
+    }
+
+    /// Every pair of nodes that mutually exclude each other (each pair only once).
+    pub fn exclusions(&self) -> Vec<(Uuid, Uuid)> {
+        self.nodes
+            .iter()
+            .flat_map(|(a, node)| {
+                node.mutually_excludes
+                    .iter()
+                    .filter(move |b| *a < **b)
+                    .map(move |b| (*a, *b))
+            })
+            .collect()
+    }
+
+    /// Takes the changes in the state of nodes (in order) since this was last called.
+    pub fn take_transitions(&mut self) -> Vec<(Uuid, Transition)> {
+        std::mem::take(&mut self.transitions)
+    }
+
     /// Add the node to the queue (and to active).
     /// This does not check any pre-requisites (unblocked and non-excluded).
     fn add_to_queue(&mut self, node: Uuid) {
@@ -68,6 +107,7 @@ impl Schedule {
 
         self.queue.push_back(node);
         assert!(self.active.insert(node));
+        self.transitions.push((node, Transition::Ready));
     }
 
     /// TODO: look at where this is used and then rename and simplify
@@ -106,6 +146,11 @@ impl Schedule {
 
         if let Some(excluding) = self.excluding.remove(&node_id) {
             for excluded_id in &excluding {
+                if !self.waiting.contains(excluded_id) {
+                    // Already queued after another excluder completed
+                    continue;
+                }
+
                 let excluded = self
                     .nodes
                     .get(excluded_id)
@@ -122,6 +167,23 @@ impl Schedule {
                     assert!(self.excluding.insert(*excluded_id, new_excluding).is_none());
                     assert!(self.waiting.remove(excluded_id));
                     self.add_to_queue(*excluded_id);
+                } else {
+                    // Still excluded by other nodes (possibly one queued in this loop) so it must
+                    // wait for those instead
+                    let excluders: Vec<Uuid> = excluded
+                        .mutually_excludes
+                        .iter()
+                        .copied()
+                        .filter(|excluder| self.active.contains(excluder))
+                        .collect();
+                    for excluder in &excluders {
+                        self.excluding
+                            .entry(*excluder)
+                            .or_default()
+                            .push(*excluded_id);
+                    }
+                    self.transitions
+                        .push((*excluded_id, Transition::Excluded { by: excluders }));
                 }
             }
         }
@@ -133,10 +195,10 @@ impl Schedule {
                 .expect("dependent must still exist");
             dependent.dependency_count -= 1;
             if dependent.dependency_count == 0 {
-                let mut excluded = false;
+                let mut excluders = Vec::new();
                 for excluder in &dependent.mutually_excludes {
                     if self.active.contains(excluder) {
-                        excluded = true;
+                        excluders.push(*excluder);
                         self.excluding
                             .entry(*excluder)
                             .or_default()
@@ -144,8 +206,10 @@ impl Schedule {
                     }
                 }
 
-                if excluded {
+                if !excluders.is_empty() {
                     assert!(self.waiting.insert(dependent_id));
+                    self.transitions
+                        .push((dependent_id, Transition::Excluded { by: excluders }));
                 } else {
                     self.add_to_queue(dependent_id);
                 }
@@ -217,6 +281,7 @@ impl ScheduleBuilder {
             queue: Default::default(),
             waiting: Default::default(),
             excluding: Default::default(),
+            transitions: Default::default(),
         };
 
         schedule.add_to_queue(START_NODE);
@@ -238,14 +303,17 @@ impl ScheduleBuilder {
 
     /// Add dependency (child depends on parent)
     pub fn add_dependency(&mut self, parent: Uuid, child: Uuid) {
+        // A node taking more than one input from the same fixture still depends on it once
+        if !self.entry(parent).dependents.insert(child) {
+            return;
+        }
+
         let child_node = self.entry(child);
         child_node.dependency_count += 1;
         // Remove child from no_dependencies if it was in there
         if child_node.dependency_count == 1 {
             self.no_dependencies.remove(&child);
         }
-
-        self.entry(parent).dependents.insert(child);
     }
 
     /// Marks two nodes as mutually exclusive
@@ -255,3 +323,114 @@ impl ScheduleBuilder {
         self.entry(b).mutually_excludes.insert(a);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the schedule to completion (one node at a time, in the order they are queued),
+    /// returning the order the nodes ran in without the start node.
+    fn run(mut schedule: Schedule) -> Vec<Uuid> {
+        let mut order = Vec::new();
+        while let NextInSchedule::Next(id) = schedule.next() {
+            schedule.complete_node(id);
+            if id != START_NODE {
+                order.push(id);
+            }
+        }
+        assert!(matches!(schedule.next(), NextInSchedule::Completed));
+
+        order
+    }
+
+    /// Takes every queued node (which are all active until completed).
+    fn drain(schedule: &mut Schedule) -> HashSet<Uuid> {
+        let mut queued = HashSet::new();
+        while let NextInSchedule::Next(id) = schedule.next() {
+            queued.insert(id);
+        }
+
+        queued
+    }
+
+    /// A waiting node that is still excluded when its excluder completes must wait for the nodes
+    /// that still exclude it, even those that were queued without knowing about it (a node queued
+    /// because its dependencies completed doesn't track the waiting nodes it excludes).
+    ///
+    /// `waiting` waits for `excluder`, `late` is queued while `waiting` waits and excludes it too.
+    #[test]
+    fn still_excluded_node_waits_for_other_excluders() {
+        let [excluder, waiting, late, waiting_dependency, late_dependency] =
+            [(); 5].map(|_| Uuid::new_v4());
+        let mut builder = ScheduleBuilder::new();
+        builder.register_node(excluder);
+        builder.add_dependency(waiting_dependency, waiting);
+        builder.add_dependency(late_dependency, late);
+        builder.add_exclusion(excluder, waiting);
+        builder.add_exclusion(late, waiting);
+        let mut schedule = builder.build();
+
+        assert_eq!(drain(&mut schedule), HashSet::from([START_NODE]));
+        schedule.complete_node(START_NODE);
+        assert_eq!(
+            drain(&mut schedule),
+            HashSet::from([excluder, waiting_dependency, late_dependency])
+        );
+
+        schedule.complete_node(waiting_dependency);
+        schedule.complete_node(late_dependency);
+        schedule.complete_node(excluder);
+        assert_eq!(drain(&mut schedule), HashSet::from([late]));
+
+        // Used to deadlock here, nothing would queue `waiting` once `late` completed
+        schedule.complete_node(late);
+        assert_eq!(drain(&mut schedule), HashSet::from([waiting]));
+        schedule.complete_node(waiting);
+        assert!(matches!(schedule.next(), NextInSchedule::Completed));
+    }
+
+    /// A node waiting for several excluders is only queued once, by the last one to complete.
+    #[test]
+    fn node_excluded_by_several_nodes_is_queued_once() {
+        let [excluder, waiting, first, second] = [(); 4].map(|_| Uuid::new_v4());
+        let [waiting_dependency, first_dependency, second_dependency] =
+            [(); 3].map(|_| Uuid::new_v4());
+        let mut builder = ScheduleBuilder::new();
+        builder.register_node(excluder);
+        builder.add_dependency(waiting_dependency, waiting);
+        builder.add_dependency(first_dependency, first);
+        builder.add_dependency(second_dependency, second);
+        builder.add_exclusion(excluder, waiting);
+        builder.add_exclusion(first, waiting);
+        builder.add_exclusion(second, waiting);
+        let mut schedule = builder.build();
+
+        drain(&mut schedule);
+        schedule.complete_node(START_NODE);
+        drain(&mut schedule);
+        schedule.complete_node(waiting_dependency);
+        schedule.complete_node(first_dependency);
+        schedule.complete_node(second_dependency);
+        schedule.complete_node(excluder);
+        assert_eq!(drain(&mut schedule), HashSet::from([first, second]));
+
+        schedule.complete_node(first);
+        assert_eq!(drain(&mut schedule), HashSet::new());
+        // `waiting` is listed by `second` twice (once for each completed excluder)
+        schedule.complete_node(second);
+        assert_eq!(drain(&mut schedule), HashSet::from([waiting]));
+        schedule.complete_node(waiting);
+        assert!(matches!(schedule.next(), NextInSchedule::Completed));
+    }
+
+    #[test]
+    fn duplicate_dependency_counts_once() {
+        let fixture = Uuid::new_v4();
+        let test = Uuid::new_v4();
+        let mut builder = ScheduleBuilder::new();
+        builder.add_dependency(fixture, test);
+        builder.add_dependency(fixture, test);
+
+        assert_eq!(run(builder.build()), [fixture, test]);
+    }
+}
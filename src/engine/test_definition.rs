@@ -9,7 +9,12 @@ pub struct TestDefinition {
 }
 
 impl TestDefinition {
-    fn fqn(&self) -> String {
-        format!("{}:{}", self.module, self.name)
+    pub fn fqn(&self) -> String {
+        fqn(&self.module, &self.name)
     }
 }
+
+/// The fully qualified name of the test or fixture `name` in the module `module_path`.
+pub(crate) fn fqn(module_path: &str, name: &str) -> String {
+    format!("{module_path}::{name}")
+}
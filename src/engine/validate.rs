@@ -0,0 +1,407 @@
+//! Checks everything that would stop the tests from running before anything runs, so every problem
+//! can be reported at once.
+
+use std::{collections::HashMap, error::Error, fmt};
+
+use uuid::Uuid;
+
+use super::resolver::{ResolveError, Resolver};
+use crate::codegen::config::{FixtureConfig, InputRef, TestConfig};
+
+/// A mistake in how the tests and fixtures are set up.
+#[derive(Debug)]
+pub enum Problem {
+    /// An input of a test or fixture couldn't be resolved.
+    Resolve(ResolveError),
+    /// Fixtures that (transitively) depend on themselves, the first fixture is repeated at the end.
+    Cycle(Vec<String>),
+    /// More than one test has the same fully qualified name.
+    DuplicateTest { name: String, count: usize },
+    /// A test or fixture takes a fixture as `&mut` more than once, or as `&mut` and as `&` or an
+    /// `Arc`, which would borrow it mutably while it's borrowed.
+    ConflictingAccess { consumer: String, fixture: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Resolve(err) => err.fmt(f),
+            Problem::Cycle(path) => write!(
+                f,
+                "fixtures depend on each other in a cycle: {}",
+                path.join(" -> ")
+            ),
+            Problem::DuplicateTest { name, count } => {
+                write!(f, "{count} tests are named `{name}`")
+            }
+            Problem::ConflictingAccess { consumer, fixture } => write!(
+                f,
+                "`{consumer}` takes `{fixture}` as `&mut` together with another borrow of it"
+            ),
+        }
+    }
+}
+
+/// Every problem found while validating.
+#[derive(Debug)]
+pub struct ValidationError {
+    pub problems: Vec<Problem>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.problems.as_slice() {
+            [problem] => problem.fmt(f),
+            problems => {
+                write!(f, "found {} problems:", problems.len())?;
+                for problem in problems {
+                    write!(f, "\n  - {problem}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Resolves the inputs of every test and fixture, collecting all of the problems found along the
+/// way.
+///
+/// Returns the fixture each input of the tests and fixtures resolved to (in the same order as the
+/// inputs), which is `None` for an optional input that no fixture provides.
+pub fn validate(
+    tests: &HashMap<Uuid, TestConfig>,
+    fixtures: &HashMap<Uuid, FixtureConfig>,
+) -> Result<HashMap<Uuid, Vec<Option<Uuid>>>, ValidationError> {
+    let mut problems = Vec::new();
+    let resolver = Resolver::new(fixtures);
+
+    let mut test_ids: Vec<_> = tests.keys().copied().collect();
+    test_ids.sort_by_key(|id| tests[id].fqn());
+    let mut fixture_ids: Vec<_> = fixtures.keys().copied().collect();
+    fixture_ids.sort_by_key(|id| fixtures[id].fqn());
+
+    let mut duplicates: Vec<(String, usize)> = Vec::new();
+    for id in &test_ids {
+        let name = tests[id].fqn();
+        match duplicates.last_mut() {
+            Some((last, count)) if *last == name => *count += 1,
+            _ => duplicates.push((name, 1)),
+        }
+    }
+    problems.extend(
+        duplicates
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(name, count)| Problem::DuplicateTest { name, count }),
+    );
+
+    let mut resolve = |inputs: &[InputRef], module_path: &str, consumer: &str| {
+        let mut resolved = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            match resolver.resolve(input.fixture(), module_path, consumer) {
+                Ok(fixture_id) => resolved.push(Some(fixture_id)),
+                Err(ResolveError::Missing { .. }) if input.is_optional() => resolved.push(None),
+                Err(err) => problems.push(Problem::Resolve(err)),
+            }
+        }
+        resolved
+    };
+
+    let resolved_tests: Vec<_> = test_ids
+        .iter()
+        .map(|id| {
+            let test = &tests[id];
+            (*id, resolve(&test.inputs, &test.module_path, &test.fqn()))
+        })
+        .collect();
+    let resolved_fixtures: HashMap<_, _> = fixture_ids
+        .iter()
+        .map(|id| {
+            let fixture = &fixtures[id];
+            (
+                *id,
+                resolve(&fixture.inputs, &fixture.module_path, &fixture.fqn()),
+            )
+        })
+        .collect();
+
+    let consumers = resolved_tests
+        .iter()
+        .map(|(id, resolved)| (tests[id].fqn(), &tests[id].inputs, resolved))
+        .chain(fixture_ids.iter().map(|id| {
+            let fixture = &fixtures[id];
+            (fixture.fqn(), &fixture.inputs, &resolved_fixtures[id])
+        }));
+    for (consumer, inputs, resolved) in consumers {
+        for fixture_id in conflicting_accesses(inputs, resolved) {
+            problems.push(Problem::ConflictingAccess {
+                consumer: consumer.clone(),
+                fixture: fixtures[&fixture_id].fqn(),
+            });
+        }
+    }
+
+    // The macro doesn't let producers take inputs yet, in which case there can't be any cycles
+    if fixtures.values().any(|fixture| !fixture.inputs.is_empty()) {
+        for cycle in cycles(&fixture_ids, &resolved_fixtures) {
+            problems.push(Problem::Cycle(
+                cycle.iter().map(|id| fixtures[id].fqn()).collect(),
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(resolved_tests
+            .into_iter()
+            .chain(resolved_fixtures)
+            .collect())
+    } else {
+        Err(ValidationError { problems })
+    }
+}
+
+/// Finds the fixtures that the inputs of one runnable take as `&mut` along with any other borrow, in
+/// the order of the inputs.
+fn conflicting_accesses(inputs: &[InputRef], resolved: &[Option<Uuid>]) -> Vec<Uuid> {
+    // Inputs that failed to resolve are left out of `resolved`, and are reported on their own
+    if inputs.len() != resolved.len() {
+        return Vec::new();
+    }
+
+    let mut borrows: Vec<(Uuid, usize, bool)> = Vec::new();
+    for (input, fixture_id) in inputs.iter().zip(resolved) {
+        let Some(fixture_id) = fixture_id else {
+            continue;
+        };
+
+        let exclusive = match input.required() {
+            InputRef::Shared(_) | InputRef::SharedArc(_) => false,
+            InputRef::Exclusive(_) => true,
+            _ => continue,
+        };
+        match borrows.iter_mut().find(|(id, _, _)| id == fixture_id) {
+            Some((_, count, any_exclusive)) => {
+                *count += 1;
+                *any_exclusive |= exclusive;
+            }
+            None => borrows.push((*fixture_id, 1, exclusive)),
+        }
+    }
+
+    borrows
+        .into_iter()
+        .filter(|(_, count, exclusive)| *count > 1 && *exclusive)
+        .map(|(id, _, _)| id)
+        .collect()
+}
+
+/// Finds the cycles in the graph of fixtures (each reported once, starting from the first fixture
+/// in `ids` that is part of it).
+fn cycles(ids: &[Uuid], inputs: &HashMap<Uuid, Vec<Option<Uuid>>>) -> Vec<Vec<Uuid>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        id: Uuid,
+        inputs: &HashMap<Uuid, Vec<Option<Uuid>>>,
+        states: &mut HashMap<Uuid, State>,
+        path: &mut Vec<Uuid>,
+        cycles: &mut Vec<Vec<Uuid>>,
+    ) {
+        match states.get(&id) {
+            Some(State::Done) => return,
+            Some(State::Visiting) => {
+                let start = path.iter().position(|other| *other == id).unwrap();
+                let mut cycle = path[start..].to_vec();
+                cycle.push(id);
+                cycles.push(cycle);
+                return;
+            }
+            None => {}
+        }
+
+        states.insert(id, State::Visiting);
+        path.push(id);
+        for input in inputs.get(&id).into_iter().flatten().flatten() {
+            visit(*input, inputs, states, path, cycles);
+        }
+        path.pop();
+        states.insert(id, State::Done);
+    }
+
+    let mut states = HashMap::new();
+    let mut cycles = Vec::new();
+    for id in ids {
+        visit(*id, inputs, &mut states, &mut Vec::new(), &mut cycles);
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::config::testing::{db, pool};
+
+    fn ids<T>(configs: Vec<T>) -> HashMap<Uuid, T> {
+        configs
+            .into_iter()
+            .map(|config| (Uuid::new_v4(), config))
+            .collect()
+    }
+
+    fn problems(tests: Vec<TestConfig>, fixtures: Vec<FixtureConfig>) -> Vec<String> {
+        match validate(&ids(tests), &ids(fixtures)) {
+            Ok(_) => Vec::new(),
+            Err(err) => err.problems.iter().map(Problem::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_missing_fixture() {
+        let problems = problems(
+            vec![TestConfig::stub(
+                "app",
+                "reads",
+                vec![InputRef::shared(db())],
+            )],
+            vec![],
+        );
+
+        assert_eq!(
+            problems,
+            [format!(
+                "`app::reads` requires `{}` but no fixture provides it",
+                db().type_name
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_ambiguous_default_providers() {
+        let problems = problems(
+            vec![TestConfig::stub(
+                "app",
+                "reads",
+                vec![InputRef::shared(db())],
+            )],
+            vec![
+                FixtureConfig::stub("app", "primary", db()),
+                FixtureConfig::stub("app", "secondary", db()),
+            ],
+        );
+
+        assert_eq!(
+            problems,
+            [format!(
+                "`app::reads` requires `{}` which is ambiguous because it is provided by \
+                 `app::primary`, `app::secondary`, use #[fixture(\"name\")] on the input to pick one",
+                db().type_name
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let problems = problems(
+            vec![
+                TestConfig::stub("app", "reads", vec![InputRef::shared(db())]),
+                TestConfig::stub("app", "reads", vec![]),
+                TestConfig::stub("app", "writes", vec![InputRef::exclusive(pool())]),
+            ],
+            vec![],
+        );
+
+        assert_eq!(
+            problems,
+            [
+                "2 tests are named `app::reads`".to_string(),
+                format!(
+                    "`app::reads` requires `{}` but no fixture provides it",
+                    db().type_name
+                ),
+                format!(
+                    "`app::writes` requires `{}` but no fixture provides it",
+                    pool().type_name
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_fixture_cycles() {
+        let mut db_fixture = FixtureConfig::stub("app", "db", db());
+        db_fixture.inputs = vec![InputRef::shared(pool())];
+        let mut pool_fixture = FixtureConfig::stub("app", "pool", pool());
+        pool_fixture.inputs = vec![InputRef::shared(db())];
+
+        let problems = problems(vec![], vec![pool_fixture, db_fixture]);
+
+        assert_eq!(
+            problems,
+            ["fixtures depend on each other in a cycle: app::db -> app::pool -> app::db"]
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_accesses() {
+        let problems = problems(
+            vec![
+                TestConfig::stub(
+                    "app",
+                    "keeps_and_writes",
+                    vec![InputRef::SharedArc(db()), InputRef::exclusive(db())],
+                ),
+                TestConfig::stub(
+                    "app",
+                    "reads_and_writes",
+                    vec![InputRef::shared(db()), InputRef::exclusive(db())],
+                ),
+                TestConfig::stub(
+                    "app",
+                    "writes_twice",
+                    vec![InputRef::exclusive(db()), InputRef::exclusive(db())],
+                ),
+            ],
+            vec![FixtureConfig::stub("app", "db", db())],
+        );
+
+        assert_eq!(
+            problems,
+            [
+                "`app::keeps_and_writes` takes `app::db` as `&mut` together with another borrow of it",
+                "`app::reads_and_writes` takes `app::db` as `&mut` together with another borrow of it",
+                "`app::writes_twice` takes `app::db` as `&mut` together with another borrow of it",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_repeated_shared_and_owned_accesses() {
+        let problems = problems(
+            vec![
+                TestConfig::stub(
+                    "app",
+                    "reads_twice",
+                    vec![InputRef::shared(db()), InputRef::SharedArc(db())],
+                ),
+                TestConfig::stub(
+                    "app",
+                    "owns_and_writes",
+                    vec![InputRef::owned(pool()), InputRef::exclusive(pool())],
+                ),
+            ],
+            vec![
+                FixtureConfig::stub("app", "db", db()),
+                FixtureConfig::stub("app", "pool", pool()),
+            ],
+        );
+
+        assert!(problems.is_empty(), "{problems:?}");
+    }
+}
@@ -1,10 +1,23 @@
 pub use critic_sys::{fixture, test};
+use std::{fs::File, io};
+
 use engine::Engine;
+use options::{Format, Options};
+use report::{
+    ConsoleReporter, HtmlReporter, JunitReporter, LibtestJsonReporter, NdjsonReporter, Reporter,
+    TapReporter, TraceReporter,
+};
 
 mod engine;
+mod options;
+pub mod report;
 
 pub(crate) mod codegen;
 
+pub use engine::factory::Factory;
+#[cfg(feature = "tracing")]
+pub use engine::records::TracingLayer;
+
 #[doc(hidden)]
 pub mod __internal {
     pub use linkme;
@@ -16,21 +29,111 @@ pub mod __internal {
         BasicRunnable, FixtureRunnable, RunnableInput, TestRunnable,
     };
 
-    pub use crate::codegen::config::{FixtureConfig, InputRef, TestConfig};
+    pub use crate::codegen::config::{FixtureConfig, FixtureRef, InputRef, TestConfig};
 }
 
+/// Runs every test, reporting the progress to the terminal and to any extra `reporters`.
 pub fn run_tests(
     tests: &[fn() -> __internal::TestConfig],
     fixtures: &[fn() -> __internal::FixtureConfig],
+    reporters: Vec<Box<dyn Reporter>>,
 ) {
-    let mut engine = Engine::new(tests, fixtures);
+    let options = match Options::from_env() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    };
+
+    for option in &options.unsupported {
+        eprintln!(
+            "warning: `{option}` is accepted for compatibility with libtest but has no effect"
+        );
+    }
+
+    let mut engine = match Engine::new(tests, fixtures, &options) {
+        Ok(engine) => engine,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    if options.list {
+        print!("{}", engine.list(options.format));
+        return;
+    }
+    if let Some(format) = options.graph {
+        engine.graph(format);
+        return;
+    }
+    if let Some(test) = &options.explain {
+        match engine.explain(test) {
+            Ok(explanation) => print!("{explanation}"),
+            Err(err) => {
+                eprintln!("error: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if options.dry_run {
+        print!("{}", engine.dry_run());
+        return;
+    }
+
+    engine::records::install(options.log_level);
+
+    let mut reporters: Vec<Box<dyn Reporter>> = match format_reporters(&options) {
+        Ok(format_reporters) => format_reporters.into_iter().chain(reporters).collect(),
+        Err(err) => {
+            eprintln!("error: failed to open the report output: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let summary = engine.run(&mut reporters);
+    if !summary.success() {
+        std::process::exit(101);
+    }
+}
+
+/// The reporters for the `--format` (and `--output`) the binary was run with, and for
+/// `--trace-out`. The terminal output is kept when the report is written to a file.
+fn format_reporters(options: &Options) -> io::Result<Vec<Box<dyn Reporter>>> {
+    let writer: Box<dyn io::Write> = match &options.output {
+        Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+
+    let mut reporters: Vec<Box<dyn Reporter>> = Vec::new();
+    if matches!(options.format, Format::Pretty | Format::Terse) || options.output.is_some() {
+        reporters.push(Box::new(ConsoleReporter::new()));
+    }
+
+    match options.format {
+        Format::Pretty | Format::Terse => {}
+        Format::Junit => reporters.push(Box::new(JunitReporter::new(writer))),
+        Format::Ndjson => reporters.push(Box::new(NdjsonReporter::new(writer))),
+        Format::Json => reporters.push(Box::new(LibtestJsonReporter::new(writer))),
+        Format::Tap => reporters.push(Box::new(TapReporter::new(writer))),
+        Format::Html => reporters.push(Box::new(HtmlReporter::new(writer))),
+    }
+
+    if let Some(path) = &options.trace_out {
+        let writer = io::BufWriter::new(File::create(path)?);
+        reporters.push(Box::new(TraceReporter::new(Box::new(writer))));
+    }
 
-    engine.run();
+    Ok(reporters)
 }
 
+/// Defines `main` for a test binary, optionally with extra reporters that receive the events of
+/// the run, e.g. `critic_test_main!(MyReporter::new())`.
 #[macro_export]
 macro_rules! critic_test_main {
-    () => {
+    ($($reporter:expr),* $(,)?) => {
         #[allow(non_camel_case_types)]
         #[doc(hidden)]
         mod __critic_test_internals {
@@ -44,6 +147,7 @@ macro_rules! critic_test_main {
             $crate::run_tests(
                 &self::__critic_test_internals::CRITIC_INTERNAL_TESTS,
                 &self::__critic_test_internals::CRITIC_INTERNAL_FIXTURES,
+                ::std::vec![$(::std::boxed::Box::new($reporter) as ::std::boxed::Box<dyn $crate::report::Reporter>),*],
             );
         }
     };
@@ -1,3 +1,11 @@
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread,
+};
+
 struct MyFixture {
     hello: String,
 }
@@ -12,6 +20,36 @@ fn produce() -> MyFixture {
 
 critic::critic_test_main!();
 
+struct Db {
+    url: String,
+}
+
+#[critic::fixture]
+fn primary_db() -> Db {
+    Db {
+        url: "postgres://primary".to_string(),
+    }
+}
+
+#[critic::fixture(profile = "ci")]
+fn ci_primary_db() -> Db {
+    Db {
+        url: "postgres://localhost:5432".to_string(),
+    }
+}
+
+#[critic::fixture(name = "replica")]
+fn replica_db() -> Db {
+    Db {
+        url: "postgres://replica".to_string(),
+    }
+}
+
+#[critic::test]
+fn reads_from_replica(primary: &Db, #[fixture("replica")] replica: &Db) {
+    println!("writing to {}, reading from {}", primary.url, replica.url);
+}
+
 #[critic::test]
 fn my_test(my_fixture: &MyFixture) {
     println!("1 hello {}", my_fixture.hello);
@@ -46,11 +84,131 @@ fn abc2() {
     println!("Hello from ABC2");
 }
 
+trait Clock: Send + Sync {
+    fn now(&self) -> u64;
+}
+
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now(&self) -> u64 {
+        1_660_000_000
+    }
+}
+
+#[critic::fixture(provides = dyn Clock)]
+fn fixed_clock() -> FixedClock {
+    FixedClock
+}
+
+#[critic::test]
+fn clock_is_injected(clock: &dyn Clock) {
+    println!("the time is {}", clock.now());
+}
+
 mod sub_module {
+    use crate::Db;
     use crate::MyFixture;
 
-    // #[critic::test]
-    // fn my_test_in_submodule(my_fixture: MyFixture) {
-    //     println!("submodule says: {}", my_fixture.hello);
-    // }
+    #[critic::fixture(override)]
+    fn unreachable_db() -> Db {
+        Db {
+            url: "postgres://unreachable".to_string(),
+        }
+    }
+
+    #[critic::test]
+    fn primary_is_overridden(primary: &Db) {
+        println!("submodule db: {}", primary.url);
+    }
+
+    #[critic::test]
+    fn my_test_in_submodule(my_fixture: MyFixture) {
+        println!("submodule says: {}", my_fixture.hello);
+    }
+}
+
+trait Greeter {
+    fn greet() -> String;
+}
+
+struct English;
+
+impl Greeter for English {
+    fn greet() -> String {
+        "hello".to_string()
+    }
+}
+
+struct French;
+
+impl Greeter for French {
+    fn greet() -> String {
+        "bonjour".to_string()
+    }
+}
+
+#[critic::test(types(English, French))]
+fn greeter_greets<G: Greeter>(my_fixture: &MyFixture) {
+    println!("{} {}", G::greet(), my_fixture.hello);
+}
+
+struct Endpoint(String);
+
+#[critic::fixture]
+fn endpoint() -> Endpoint {
+    Endpoint("https://example.com".to_string())
+}
+
+#[critic::test]
+fn destructures_inputs(Endpoint(url): &Endpoint, MyFixture { hello }: &MyFixture) {
+    println!("{hello} from {url}");
+}
+
+#[critic::test]
+fn shares_fixtures_with_threads(#[fixture("replica")] replica: Arc<Db>, clock: Arc<dyn Clock>) {
+    let handle = thread::spawn(move || format!("{} at {}", replica.url, clock.now()));
+    println!("from a thread: {}", handle.join().unwrap());
+}
+
+/// A local stand-in for a cache, only provided when the `cache` profile is active
+struct Cache {
+    url: String,
+}
+
+#[critic::fixture(profile = "cache")]
+fn local_cache() -> Cache {
+    Cache {
+        url: "redis://localhost:6379".to_string(),
+    }
+}
+
+#[critic::test]
+fn adapts_to_missing_cache(cache: Option<&Cache>, endpoint: Option<&mut Endpoint>) {
+    match cache {
+        Some(cache) => println!("caching in {}", cache.url),
+        None => println!("running without a cache"),
+    }
+    if let Some(Endpoint(url)) = endpoint {
+        url.push_str("/cached");
+    }
+}
+
+struct Client {
+    id: u32,
+}
+
+#[critic::fixture]
+fn client() -> Client {
+    static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+    Client {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+    }
+}
+
+#[critic::test]
+fn clients_talk_to_each_other(clients: critic::Factory<Client>) {
+    let (alice, bob, carol) = (clients.create(), clients.create(), clients.create());
+    println!("{} -> {} -> {}", alice.id, bob.id, carol.id);
+    assert!(alice.id != bob.id && bob.id != carol.id);
 }
@@ -0,0 +1,386 @@
+use std::{env, error::Error, fmt, path::PathBuf};
+
+use crate::report::Level;
+
+/// The environment variable used to select a fixture profile when `--profile` is not given.
+pub const PROFILE_ENV: &str = "CRITIC_PROFILE";
+
+/// Options for a run of the test binary, parsed from the command line and the environment.
+#[derive(Debug, Default)]
+pub struct Options {
+    /// The profile used to pick between alternative fixture providers
+    pub profile: Option<String>,
+    /// List the tests and fixtures (and how they are connected) instead of running them
+    pub list: bool,
+    /// Print the waves the tests would run in instead of running them
+    pub dry_run: bool,
+    /// Print how this test would be scheduled instead of running the tests
+    pub explain: Option<String>,
+    /// Print the graph of tests and fixtures in this format instead of running them
+    pub graph: Option<GraphFormat>,
+    /// The format of the report
+    pub format: Format,
+    /// The file the report is written to (instead of stdout)
+    pub output: Option<PathBuf>,
+    /// Only run the tests whose name contains one of these
+    pub filters: Vec<String>,
+    /// Filters must match the name of a test exactly
+    pub exact: bool,
+    /// The least severe level of the log records that are collected
+    pub log_level: Level,
+    /// Pass the output of tests and fixtures straight through instead of capturing it
+    pub nocapture: bool,
+    /// The file a Chrome trace of the run is written to
+    pub trace_out: Option<PathBuf>,
+    /// Only run the ignored tests (libtest's `--ignored`), critic doesn't ignore tests so none run
+    pub ignored: bool,
+    /// The libtest options that were given but have no effect, which are warned about
+    pub unsupported: Vec<&'static str>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Human readable output
+    #[default]
+    Pretty,
+    /// A line per test with `--list` (otherwise the same as `pretty`)
+    Terse,
+    /// JUnit XML
+    Junit,
+    /// critic's own versioned stream of events as newline-delimited JSON
+    Ndjson,
+    /// The JSON events of libtest (`--format json -Z unstable-options`) for IDEs and other tools
+    Json,
+    /// Test Anything Protocol
+    Tap,
+    /// A self contained HTML page
+    Html,
+}
+
+impl Format {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pretty" => Some(Format::Pretty),
+            "terse" => Some(Format::Terse),
+            "junit" => Some(Format::Junit),
+            "ndjson" => Some(Format::Ndjson),
+            "json" => Some(Format::Json),
+            "tap" => Some(Format::Tap),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "dot" => Some(GraphFormat::Dot),
+            "mermaid" => Some(GraphFormat::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum OptionsError {
+    /// An argument that critic doesn't understand
+    Unknown(String),
+    /// An option that requires a value was given without one
+    MissingValue(&'static str),
+    /// The value given to an option is not one it accepts
+    InvalidValue { option: &'static str, value: String },
+    /// `--output` was given with a format (`pretty` or `terse`) that is only written to the terminal
+    OutputWithoutFile(&'static str),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::Unknown(arg) => write!(f, "unknown argument `{arg}`"),
+            OptionsError::MissingValue(option) => write!(f, "`{option}` requires a value"),
+            OptionsError::InvalidValue { option, value } => {
+                write!(f, "`{value}` is not a valid value for `{option}`")
+            }
+            OptionsError::OutputWithoutFile(format) => write!(
+                f,
+                "`--output` can't be used with `--format {format}`, which is only written to the \
+                 terminal"
+            ),
+        }
+    }
+}
+
+impl Error for OptionsError {}
+
+impl Options {
+    /// Reads the options from the arguments given to the binary and the environment.
+    pub fn from_env() -> Result<Self, OptionsError> {
+        let mut options = Options::parse(env::args().skip(1))?;
+
+        if options.profile.is_none() {
+            options.profile = env::var(PROFILE_ENV)
+                .ok()
+                .filter(|profile| !profile.is_empty());
+        }
+
+        Ok(options)
+    }
+
+    /// Whether the test `name` in `module_path` is selected by the filters.
+    ///
+    /// An exact filter can also leave out the name of the crate (as libtest names don't include
+    /// it).
+    pub fn matches_filters(&self, module_path: &str, name: &str) -> bool {
+        if self.ignored {
+            return false;
+        }
+        if self.filters.is_empty() {
+            return true;
+        }
+
+        let fqn = format!("{module_path}::{name}");
+        let in_crate = libtest_name(module_path, name);
+
+        self.filters.iter().any(|filter| {
+            if self.exact {
+                filter == &fqn || *filter == in_crate
+            } else {
+                fqn.contains(filter.as_str())
+            }
+        })
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, OptionsError> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') {
+                options.filters.push(arg);
+                continue;
+            }
+
+            // Accepted for compatibility with libtest (which requires it for `--format json`)
+            if let Some(flag) = arg.strip_prefix("-Z").filter(|flag| !flag.is_empty()) {
+                check_unstable_flag(flag)?;
+                continue;
+            }
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+
+            let mut value = |option: &'static str| {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or(OptionsError::MissingValue(option))
+            };
+
+            match name.as_str() {
+                "--profile" => options.profile = Some(value("--profile")?),
+                "--list" => options.list = true,
+                "--dry-run" => options.dry_run = true,
+                "--explain" => options.explain = Some(value("--explain")?),
+                "--format" => {
+                    let value = value("--format")?;
+                    options.format = Format::parse(&value).ok_or(OptionsError::InvalidValue {
+                        option: "--format",
+                        value,
+                    })?;
+                }
+                "--graph" => {
+                    let value = value("--graph")?;
+                    options.graph = Some(GraphFormat::parse(&value).ok_or(
+                        OptionsError::InvalidValue {
+                            option: "--graph",
+                            value,
+                        },
+                    )?);
+                }
+                "--output" => options.output = Some(value("--output")?.into()),
+                "--exact" => options.exact = true,
+                "--nocapture" => options.nocapture = true,
+                "--log-level" => {
+                    let value = value("--log-level")?;
+                    options.log_level = parse_level(&value).ok_or(OptionsError::InvalidValue {
+                        option: "--log-level",
+                        value,
+                    })?;
+                }
+                "--trace-out" => options.trace_out = Some(value("--trace-out")?.into()),
+                // Accepted for compatibility with libtest (e.g. when run through `cargo test`)
+                "--ignored" => options.ignored = true,
+                "--include-ignored" => options.unsupported.push("--include-ignored"),
+                "--show-output" => options.unsupported.push("--show-output"),
+                "--test-threads" => {
+                    let value = value("--test-threads")?;
+                    if !value.parse::<usize>().is_ok_and(|threads| threads > 0) {
+                        return Err(OptionsError::InvalidValue {
+                            option: "--test-threads",
+                            value,
+                        });
+                    }
+                    options.unsupported.push("--test-threads");
+                }
+                "-Z" => check_unstable_flag(&value("-Z")?)?,
+                _ => return Err(OptionsError::Unknown(arg)),
+            }
+        }
+
+        let terminal_format = match options.format {
+            Format::Pretty => Some("pretty"),
+            Format::Terse => Some("terse"),
+            _ => None,
+        };
+        if let (Some(format), Some(_)) = (terminal_format, &options.output) {
+            return Err(OptionsError::OutputWithoutFile(format));
+        }
+
+        Ok(options)
+    }
+}
+
+/// The name libtest gives the test `name` in `module_path`, which is the path from the root of the
+/// crate (without the name of the crate).
+pub fn libtest_name(module_path: &str, name: &str) -> String {
+    match module_path.split_once("::") {
+        Some((_, path)) => format!("{path}::{name}"),
+        None => name.to_string(),
+    }
+}
+
+fn parse_level(value: &str) -> Option<Level> {
+    match value.to_ascii_lowercase().as_str() {
+        "error" => Some(Level::Error),
+        "warn" => Some(Level::Warn),
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+fn check_unstable_flag(flag: &str) -> Result<(), OptionsError> {
+    if flag == "unstable-options" {
+        Ok(())
+    } else {
+        Err(OptionsError::InvalidValue {
+            option: "-Z",
+            value: flag.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, OptionsError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_values_inline_and_separate() {
+        let options = parse(&["--profile=ci", "--format", "junit", "--output", "out.xml"]).unwrap();
+
+        assert_eq!(options.profile.as_deref(), Some("ci"));
+        assert_eq!(options.format, Format::Junit);
+        assert_eq!(options.output, Some(PathBuf::from("out.xml")));
+    }
+
+    #[test]
+    fn collects_filters() {
+        let options = parse(&["db", "--exact", "cache"]).unwrap();
+
+        assert_eq!(options.filters, ["db", "cache"]);
+        assert!(options.exact);
+    }
+
+    #[test]
+    fn rejects_unknown_and_invalid_arguments() {
+        assert!(matches!(
+            parse(&["--bogus"]),
+            Err(OptionsError::Unknown(arg)) if arg == "--bogus"
+        ));
+        assert!(matches!(
+            parse(&["--format", "xml"]),
+            Err(OptionsError::InvalidValue {
+                option: "--format",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["--log-level"]),
+            Err(OptionsError::MissingValue("--log-level"))
+        ));
+        assert!(matches!(
+            parse(&["-Z", "bogus"]),
+            Err(OptionsError::InvalidValue { option: "-Z", .. })
+        ));
+        assert!(matches!(
+            parse(&["--output", "report.txt"]),
+            Err(OptionsError::OutputWithoutFile("pretty"))
+        ));
+        assert!(parse(&["--format", "junit", "--output", "report.xml"]).is_ok());
+    }
+
+    #[test]
+    fn accepts_libtest_flags() {
+        let options = parse(&[
+            "--format=json",
+            "-Zunstable-options",
+            "--show-output",
+            "--test-threads=4",
+            "--include-ignored",
+        ])
+        .unwrap();
+
+        assert_eq!(options.format, Format::Json);
+        assert!(options.matches_filters("app::db", "reads"));
+        assert_eq!(
+            options.unsupported,
+            ["--show-output", "--test-threads", "--include-ignored"]
+        );
+        assert!(matches!(
+            parse(&["--test-threads", "0"]),
+            Err(OptionsError::InvalidValue {
+                option: "--test-threads",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn ignored_selects_no_tests() {
+        let options = parse(&["--ignored"]).unwrap();
+
+        assert!(!options.matches_filters("app::db", "reads"));
+    }
+
+    #[test]
+    fn exact_filter_matches_with_and_without_crate() {
+        let options = parse(&["--exact", "db::reads"]).unwrap();
+
+        assert!(options.matches_filters("app::db", "reads"));
+        assert!(!options.matches_filters("app::db", "reads_more"));
+        assert!(parse(&["--exact", "app::db::reads"])
+            .unwrap()
+            .matches_filters("app::db", "reads"));
+    }
+
+    #[test]
+    fn libtest_name_leaves_out_crate() {
+        assert_eq!(libtest_name("app::db", "reads"), "db::reads");
+        assert_eq!(libtest_name("app", "reads"), "reads");
+    }
+}
@@ -0,0 +1,372 @@
+//! Structured events describing a run, and the reporters that consume them.
+
+use std::{fmt, time::Duration};
+
+use uuid::Uuid;
+
+pub use crate::engine::runner::Transition;
+use crate::engine::test_definition;
+
+mod console;
+mod html;
+mod junit;
+mod libtest;
+pub mod ndjson;
+mod progress;
+mod tap;
+mod trace;
+
+pub use console::ConsoleReporter;
+pub use html::HtmlReporter;
+pub use junit::JunitReporter;
+pub use libtest::LibtestJsonReporter;
+pub use ndjson::NdjsonReporter;
+pub use tap::TapReporter;
+pub use trace::TraceReporter;
+
+/// Receives the events of a run as they happen.
+pub trait Reporter {
+    fn report(&mut self, event: &Event<'_>);
+}
+
+impl<R: Reporter + ?Sized> Reporter for Box<R> {
+    fn report(&mut self, event: &Event<'_>) {
+        (**self).report(event)
+    }
+}
+
+impl<R: Reporter> Reporter for Vec<R> {
+    fn report(&mut self, event: &Event<'_>) {
+        for reporter in self {
+            reporter.report(event);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Event<'a> {
+    /// The run is about to start, the plan contains everything that will run.
+    RunStarted { run: &'a RunInfo, plan: &'a Plan },
+    FixtureStarted {
+        fixture: &'a FixtureInfo,
+        /// The index of the worker thread running the fixture
+        worker: usize,
+    },
+    FixtureFinished {
+        fixture: &'a FixtureInfo,
+        outcome: &'a Outcome,
+        duration: Duration,
+        output: &'a Output,
+    },
+    TestStarted {
+        test: &'a TestInfo,
+        /// The index of the worker thread running the test
+        worker: usize,
+    },
+    TestFinished {
+        test: &'a TestInfo,
+        outcome: &'a Outcome,
+        duration: Duration,
+        output: &'a Output,
+    },
+    /// The state of a test or fixture changed in the schedule.
+    Scheduled {
+        id: Uuid,
+        transition: &'a Transition,
+    },
+    /// Every test has finished.
+    RunFinished { summary: &'a Summary },
+}
+
+/// Escapes text for use in XML (and HTML) attributes and elements.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The test or fixture panicked or returned an error.
+    Failed {
+        message: String,
+    },
+    /// The test could not run because a fixture it depends on failed.
+    Errored {
+        message: String,
+    },
+}
+
+/// What a test or fixture printed while it ran, empty unless output is captured.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Output {
+    pub stdout: String,
+    pub stderr: String,
+    /// The `log` records and `tracing` events (with the `log` and `tracing` features)
+    pub records: Vec<Record>,
+}
+
+impl Output {
+    pub fn is_empty(&self) -> bool {
+        self.stdout.is_empty() && self.stderr.is_empty() && self.records.is_empty()
+    }
+}
+
+/// A log record (or tracing event) emitted by a test or fixture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub level: Level,
+    /// The module (or other target) the record came from
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} {}] {}", self.level, self.target, self.message)
+    }
+}
+
+/// The level of a record, from the most to the least severe.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How the run was configured
+#[derive(Clone, Debug, Default)]
+pub struct RunInfo {
+    /// The number of threads tests are run on
+    pub threads: usize,
+    /// The seed used to shuffle the order of the tests (`None` when the order isn't shuffled)
+    pub seed: Option<u64>,
+    /// The filters used to select which tests run
+    pub filters: Vec<String>,
+    /// The number of tests that didn't match the filters
+    pub filtered_out: usize,
+    /// The active fixture profile
+    pub profile: Option<String>,
+    /// Whether the output of tests and fixtures is captured (rather than passed through)
+    pub capture: bool,
+}
+
+/// The tests and fixtures that are going to run.
+#[derive(Clone, Debug, Default)]
+pub struct Plan {
+    pub groups: Vec<GroupInfo>,
+    pub tests: Vec<TestInfo>,
+    pub fixtures: Vec<FixtureInfo>,
+}
+
+/// The tests of a single module
+#[derive(Clone, Debug)]
+pub struct GroupInfo {
+    /// The full path of the module
+    pub name: String,
+    pub tests: Vec<Uuid>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestInfo {
+    pub id: Uuid,
+    /// The full path of the module the test is in
+    pub module_path: String,
+    pub name: String,
+    /// The fixtures given to the test (in the same order as its inputs)
+    pub fixtures: Vec<Uuid>,
+}
+
+impl TestInfo {
+    /// The fully qualified name of the test
+    pub fn fqn(&self) -> String {
+        test_definition::fqn(&self.module_path, &self.name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FixtureInfo {
+    pub id: Uuid,
+    /// The full path of the module the producer function is in
+    pub module_path: String,
+    /// The name of the producer function
+    pub name: String,
+    /// The type the fixture is provided as
+    pub type_name: &'static str,
+}
+
+impl FixtureInfo {
+    /// The fully qualified name of the producer function
+    pub fn fqn(&self) -> String {
+        test_definition::fqn(&self.module_path, &self.name)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub errored: usize,
+    pub duration: Duration,
+}
+
+impl Summary {
+    /// Whether every test passed
+    pub fn success(&self) -> bool {
+        self.failed == 0 && self.errored == 0
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::{cell::RefCell, io, rc::Rc, time::Duration};
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    /// A writer that can still be read after it is given to a reporter.
+    #[derive(Clone, Default)]
+    pub struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Buffer {
+        pub fn boxed(&self) -> Box<dyn io::Write> {
+            Box::new(self.clone())
+        }
+
+        pub fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Reports a run of `app::db` (which fails) and three tests in `app`: `passes`, `fails` and
+    /// `errors` (which takes the failing fixture).
+    pub fn report_run(reporter: &mut dyn Reporter) {
+        let fixture = FixtureInfo {
+            id: Uuid::new_v4(),
+            module_path: "app".to_string(),
+            name: "db".to_string(),
+            type_name: "app::Db",
+        };
+        let test = |name: &str, fixtures: Vec<Uuid>| TestInfo {
+            id: Uuid::new_v4(),
+            module_path: "app".to_string(),
+            name: name.to_string(),
+            fixtures,
+        };
+        let tests = vec![
+            test("errors", vec![fixture.id]),
+            test("fails", vec![]),
+            test("passes", vec![]),
+        ];
+        let plan = Plan {
+            groups: vec![GroupInfo {
+                name: "app".to_string(),
+                tests: tests.iter().map(|test| test.id).collect(),
+            }],
+            tests,
+            fixtures: vec![fixture],
+        };
+        let run = RunInfo {
+            threads: 1,
+            capture: true,
+            ..RunInfo::default()
+        };
+        let duration = Duration::from_millis(5);
+        let output = Output {
+            stdout: "to stdout\n".to_string(),
+            ..Output::default()
+        };
+
+        reporter.report(&Event::RunStarted {
+            run: &run,
+            plan: &plan,
+        });
+
+        let fixture = &plan.fixtures[0];
+        reporter.report(&Event::FixtureStarted { fixture, worker: 0 });
+        reporter.report(&Event::FixtureFinished {
+            fixture,
+            outcome: &Outcome::Failed {
+                message: "no database".to_string(),
+            },
+            duration,
+            output: &Output::default(),
+        });
+
+        let outcomes = [
+            Outcome::Errored {
+                message: "fixture `app::db` failed: no database".to_string(),
+            },
+            Outcome::Failed {
+                message: "panicked: assertion failed".to_string(),
+            },
+            Outcome::Passed,
+        ];
+        for (test, outcome) in plan.tests.iter().zip(&outcomes) {
+            reporter.report(&Event::TestStarted { test, worker: 0 });
+            reporter.report(&Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output: &output,
+            });
+        }
+
+        reporter.report(&Event::RunFinished {
+            summary: &Summary {
+                passed: 1,
+                failed: 1,
+                errored: 1,
+                duration: Duration::from_millis(20),
+            },
+        });
+    }
+}
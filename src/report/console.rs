@@ -0,0 +1,200 @@
+use std::io::{self, IsTerminal};
+
+use super::{progress::Progress, Event, Outcome, Output, Reporter};
+
+/// Prints a line per test (in the style of libtest) and a summary of the failures at the end,
+/// along with anything they printed.
+///
+/// On a terminal it also keeps a live progress region below the lines and prints failures in full
+/// as they happen.
+#[derive(Default)]
+pub struct ConsoleReporter {
+    failures: Vec<(String, String, Output)>,
+    filtered_out: usize,
+    live: bool,
+    progress: Option<Progress>,
+}
+
+impl ConsoleReporter {
+    pub fn new() -> Self {
+        ConsoleReporter {
+            live: io::stdout().is_terminal(),
+            ..Default::default()
+        }
+    }
+
+    /// Prints a line above the progress region (if there is one).
+    fn println(&self, line: &str) {
+        match &self.progress {
+            Some(progress) => progress.println(line),
+            None => println!("{line}"),
+        }
+    }
+
+    fn failed(&mut self, line: String, name: String, message: &str, output: &Output) {
+        self.println(&line);
+        if self.progress.is_some() {
+            self.println(&failure(&name, message, output));
+        }
+        self.failures
+            .push((name, message.to_string(), output.clone()));
+    }
+}
+
+/// The details of a failure, with the captured output after the message.
+fn failure(name: &str, message: &str, output: &Output) -> String {
+    let mut text = format!("---- {name} ----\n{message}\n");
+    for (stream, captured) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        if !captured.is_empty() {
+            text.push_str(&format!("---- {name} {stream} ----\n{captured}"));
+            if !captured.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+    }
+    if !output.records.is_empty() {
+        text.push_str(&format!("---- {name} logs ----\n"));
+        for record in &output.records {
+            text.push_str(&format!("{record}\n"));
+        }
+    }
+
+    text
+}
+
+impl Reporter for ConsoleReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        match event {
+            Event::RunStarted { run, plan } => {
+                self.filtered_out = run.filtered_out;
+                let plural = if plan.tests.len() == 1 { "" } else { "s" };
+                println!("\nrunning {} test{plural}", plan.tests.len());
+
+                // Output that isn't captured would be mixed up with the progress region
+                if self.live && run.capture {
+                    self.progress = Some(Progress::start(plan.tests.len()));
+                }
+            }
+            Event::FixtureStarted { fixture, .. } => {
+                if let Some(progress) = &self.progress {
+                    progress.started(fixture.fqn());
+                }
+            }
+            Event::TestStarted { test, .. } => {
+                if let Some(progress) = &self.progress {
+                    progress.started(test.fqn());
+                }
+            }
+            Event::FixtureFinished {
+                fixture,
+                outcome,
+                output,
+                ..
+            } => {
+                if let Some(progress) = &self.progress {
+                    progress.finished(&fixture.fqn(), None);
+                }
+
+                if let Outcome::Failed { message } = outcome {
+                    let line = format!("fixture {} ... FAILED", fixture.fqn());
+                    self.failed(line, fixture.fqn(), message, output);
+                }
+            }
+            Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output,
+            } => {
+                if let Some(progress) = &self.progress {
+                    progress.finished(&test.fqn(), Some((outcome, *duration)));
+                }
+
+                match outcome {
+                    Outcome::Passed => self.println(&format!("test {} ... ok", test.fqn())),
+                    Outcome::Failed { message } => {
+                        let line = format!("test {} ... FAILED", test.fqn());
+                        self.failed(line, test.fqn(), message, output);
+                    }
+                    // The failed fixture is reported on its own
+                    Outcome::Errored { .. } => {
+                        self.println(&format!("test {} ... ERROR", test.fqn()))
+                    }
+                }
+            }
+            Event::RunFinished { summary } => {
+                // Failures were already printed in full as they happened when live
+                let live = self.progress.take().is_some();
+
+                if !self.failures.is_empty() {
+                    println!("\nfailures:\n");
+                    for (name, message, output) in &self.failures {
+                        if live {
+                            println!("    {name}");
+                        } else {
+                            println!("{}", failure(name, message, output));
+                        }
+                    }
+                }
+
+                println!(
+                    "\ntest result: {}. {} passed; {} failed; {} errored; {} filtered out; finished in {:.2}s\n",
+                    if summary.success() { "ok" } else { "FAILED" },
+                    summary.passed,
+                    summary.failed,
+                    summary.errored,
+                    self.filtered_out,
+                    summary.duration.as_secs_f64()
+                );
+            }
+            Event::Scheduled { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{testing::report_run, Level, Record};
+
+    #[test]
+    fn collects_failed_tests_and_fixtures() {
+        let mut reporter = ConsoleReporter::default();
+        report_run(&mut reporter);
+
+        let names: Vec<_> = reporter
+            .failures
+            .iter()
+            .map(|(name, message, _)| (name.as_str(), message.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("app::db", "no database"),
+                ("app::fails", "panicked: assertion failed")
+            ]
+        );
+    }
+
+    #[test]
+    fn prints_the_output_after_a_failure() {
+        let output = Output {
+            stdout: "to stdout".to_string(),
+            stderr: String::new(),
+            records: vec![Record {
+                level: Level::Warn,
+                target: "app".to_string(),
+                message: "slow query".to_string(),
+            }],
+        };
+
+        assert_eq!(
+            failure("app::fails", "panicked", &output),
+            format!(
+                "---- app::fails ----\npanicked\n---- app::fails stdout ----\nto stdout\n\
+                 ---- app::fails logs ----\n{}\n",
+                output.records[0]
+            )
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use super::{escape_xml, Event, Outcome, Output, Plan, Reporter, Summary};
+
+/// The width of the timeline and graph drawings in pixels
+const WIDTH: f64 = 1000.0;
+/// The height of a row in the timeline and graph drawings in pixels
+const ROW_HEIGHT: f64 = 24.0;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+td, th { text-align: left; padding: 0.2em 0.6em; border-bottom: 1px solid #ddd; }
+pre { background: #f6f6f6; padding: 0.6em; overflow-x: auto; }
+summary { font-weight: bold; cursor: pointer; margin: 0.4em 0; }
+.passed { color: #1a7f37; }
+.failed, .errored { color: #cf222e; }
+.skipped { color: #9a6700; }
+svg text { font-size: 12px; }
+";
+
+/// A test or fixture that ran
+struct Span {
+    worker: usize,
+    start: Duration,
+    duration: Duration,
+    outcome: Option<Outcome>,
+    output: Output,
+}
+
+/// Writes a self contained HTML report once the run has finished, with the results per module, a
+/// timeline of each worker and the graph of which tests use which fixtures.
+pub struct HtmlReporter {
+    writer: Box<dyn Write>,
+    plan: Plan,
+    start: Instant,
+    spans: HashMap<Uuid, Span>,
+}
+
+impl HtmlReporter {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        HtmlReporter {
+            writer,
+            plan: Plan::default(),
+            start: Instant::now(),
+            spans: HashMap::new(),
+        }
+    }
+
+    fn start_span(&mut self, id: Uuid, worker: usize) {
+        self.spans.insert(
+            id,
+            Span {
+                worker,
+                start: self.start.elapsed(),
+                duration: Duration::ZERO,
+                outcome: None,
+                output: Output::default(),
+            },
+        );
+    }
+
+    fn finish_span(&mut self, id: Uuid, outcome: &Outcome, duration: Duration, output: &Output) {
+        if let Some(span) = self.spans.get_mut(&id) {
+            span.duration = duration;
+            span.outcome = Some(outcome.clone());
+            span.output = output.clone();
+        }
+    }
+
+    fn names(&self) -> HashMap<Uuid, String> {
+        let tests = self.plan.tests.iter().map(|test| (test.id, test.fqn()));
+        let fixtures = self
+            .plan
+            .fixtures
+            .iter()
+            .map(|fixture| (fixture.id, fixture.fqn()));
+
+        tests.chain(fixtures).collect()
+    }
+
+    fn results(&self) -> String {
+        let mut html = String::new();
+        let tests: HashMap<_, _> = self.plan.tests.iter().map(|test| (test.id, test)).collect();
+
+        for group in &self.plan.groups {
+            let failures = group
+                .tests
+                .iter()
+                .filter(|id| {
+                    matches!(
+                        self.spans.get(id).and_then(|span| span.outcome.as_ref()),
+                        Some(Outcome::Failed { .. } | Outcome::Errored { .. })
+                    )
+                })
+                .count();
+            let class = if failures == 0 { "passed" } else { "failed" };
+
+            let _ = writeln!(
+                html,
+                r#"<details open><summary class="{class}">{} ({} tests, {failures} failed)</summary>"#,
+                escape_xml(&group.name),
+                group.tests.len(),
+            );
+            html.push_str("<table><tr><th>Test</th><th>Outcome</th><th>Duration</th></tr>\n");
+
+            for id in &group.tests {
+                let test = tests[id];
+                let span = self.spans.get(id);
+                let (outcome, message) = match span.and_then(|span| span.outcome.as_ref()) {
+                    Some(Outcome::Passed) => ("passed", None),
+                    Some(Outcome::Failed { message }) => ("failed", Some(message)),
+                    Some(Outcome::Errored { message }) => ("errored", Some(message)),
+                    None => ("skipped", None),
+                };
+                let duration = span.map(|span| span.duration).unwrap_or_default();
+
+                let _ = writeln!(
+                    html,
+                    r#"<tr><td>{}</td><td class="{outcome}">{outcome}</td><td>{:.3}s</td></tr>"#,
+                    escape_xml(&test.name),
+                    duration.as_secs_f64()
+                );
+                if let Some(message) = message {
+                    let _ = writeln!(
+                        html,
+                        r#"<tr><td colspan="3"><pre>{}</pre></td></tr>"#,
+                        escape_xml(message)
+                    );
+                }
+                if let Some(output) = span.map(|span| &span.output).filter(|o| !o.is_empty()) {
+                    let _ = writeln!(
+                        html,
+                        r#"<tr><td colspan="3"><details><summary>output</summary><pre>{}{}{}</pre></details></td></tr>"#,
+                        escape_xml(&output.stdout),
+                        escape_xml(&output.stderr),
+                        escape_xml(
+                            &output
+                                .records
+                                .iter()
+                                .map(|record| format!("{record}\n"))
+                                .collect::<String>()
+                        )
+                    );
+                }
+            }
+
+            html.push_str("</table></details>\n");
+        }
+
+        html
+    }
+
+    /// An SVG with a row per worker and a bar for each test and fixture it ran.
+    fn timeline(&self, total: Duration) -> String {
+        let names = self.names();
+        let workers = self
+            .spans
+            .values()
+            .map(|span| span.worker + 1)
+            .max()
+            .unwrap_or(1);
+        let label_width = 80.0;
+        let scale = (WIDTH - label_width) / total.as_secs_f64().max(f64::EPSILON);
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{}">"#,
+            workers as f64 * ROW_HEIGHT + ROW_HEIGHT
+        );
+
+        for worker in 0..workers {
+            let _ = write!(
+                svg,
+                r#"<text x="0" y="{}">worker {worker}</text>"#,
+                worker as f64 * ROW_HEIGHT + 16.0
+            );
+        }
+
+        let mut spans: Vec<_> = self.spans.iter().collect();
+        spans.sort_by_key(|(_, span)| span.start);
+        for (id, span) in spans {
+            let colour = match span.outcome {
+                Some(Outcome::Passed) => "#4ac26b",
+                Some(_) => "#ff8182",
+                None => "#d4a72c",
+            };
+
+            let _ = write!(
+                svg,
+                r##"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{colour}" stroke="#fff"><title>{} ({:.3}s)</title></rect>"##,
+                label_width + span.start.as_secs_f64() * scale,
+                span.worker as f64 * ROW_HEIGHT + 2.0,
+                (span.duration.as_secs_f64() * scale).max(1.0),
+                ROW_HEIGHT - 4.0,
+                escape_xml(&names[id]),
+                span.duration.as_secs_f64()
+            );
+        }
+
+        let _ = write!(
+            svg,
+            r#"<text x="{label_width}" y="{}">0s</text><text x="{}" y="{}" text-anchor="end">{:.3}s</text></svg>"#,
+            workers as f64 * ROW_HEIGHT + 16.0,
+            WIDTH,
+            workers as f64 * ROW_HEIGHT + 16.0,
+            total.as_secs_f64()
+        );
+
+        svg
+    }
+
+    /// An SVG with the fixtures on the left joined to the tests that use them on the right.
+    fn fixture_graph(&self) -> String {
+        let rows = self.plan.tests.len().max(self.plan.fixtures.len());
+        let fixture_x = 10.0;
+        let test_x = WIDTH / 2.0;
+        let y = |row: usize| row as f64 * ROW_HEIGHT + 16.0;
+
+        let fixture_rows: HashMap<_, _> = self
+            .plan
+            .fixtures
+            .iter()
+            .enumerate()
+            .map(|(row, fixture)| (fixture.id, row))
+            .collect();
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{}">"#,
+            rows as f64 * ROW_HEIGHT + ROW_HEIGHT
+        );
+
+        for (row, test) in self.plan.tests.iter().enumerate() {
+            for fixture in &test.fixtures {
+                let _ = write!(
+                    svg,
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#888\"/>",
+                    test_x - 150.0,
+                    y(fixture_rows[fixture]) - 4.0,
+                    test_x - 10.0,
+                    y(row) - 4.0
+                );
+            }
+
+            let _ = write!(
+                svg,
+                r#"<text x="{test_x}" y="{}">{}</text>"#,
+                y(row),
+                escape_xml(&test.fqn())
+            );
+        }
+
+        for (row, fixture) in self.plan.fixtures.iter().enumerate() {
+            let _ = write!(
+                svg,
+                r#"<text x="{fixture_x}" y="{}">{}: {}</text>"#,
+                y(row),
+                escape_xml(&fixture.fqn()),
+                escape_xml(fixture.type_name)
+            );
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    fn write_report(&mut self, summary: &Summary) -> io::Result<()> {
+        let status = if summary.success() {
+            "passed"
+        } else {
+            "failed"
+        };
+
+        let html = format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>critic report</title>
+<style>{STYLE}</style>
+</head>
+<body>
+<h1>critic report</h1>
+<p class="{status}">{} passed, {} failed, {} errored in {:.3}s</p>
+<h2>Results</h2>
+{}
+<h2>Timeline</h2>
+{}
+<h2>Fixtures</h2>
+{}
+</body>
+</html>
+"#,
+            summary.passed,
+            summary.failed,
+            summary.errored,
+            summary.duration.as_secs_f64(),
+            self.results(),
+            self.timeline(summary.duration),
+            self.fixture_graph()
+        );
+
+        self.writer.write_all(html.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+impl Reporter for HtmlReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        match event {
+            Event::RunStarted { plan, .. } => {
+                self.plan = (*plan).clone();
+                self.start = Instant::now();
+            }
+            Event::FixtureStarted { fixture, worker } => self.start_span(fixture.id, *worker),
+            Event::TestStarted { test, worker } => self.start_span(test.id, *worker),
+            Event::FixtureFinished {
+                fixture,
+                outcome,
+                duration,
+                output,
+            } => self.finish_span(fixture.id, outcome, *duration, output),
+            Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output,
+            } => self.finish_span(test.id, outcome, *duration, output),
+            Event::RunFinished { summary } => {
+                if let Err(err) = self.write_report(summary) {
+                    eprintln!("error: failed to write the HTML report: {err}");
+                }
+            }
+            Event::Scheduled { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::testing::{report_run, Buffer};
+
+    #[test]
+    fn writes_a_self_contained_report() {
+        let buffer = Buffer::default();
+        report_run(&mut HtmlReporter::new(buffer.boxed()));
+        let html = buffer.contents();
+
+        assert!(html.contains(r#"<p class="failed">1 passed, 1 failed, 1 errored in 0.020s</p>"#));
+        assert!(!html.contains("src=") && !html.contains("href="));
+
+        // A bar on the timeline for every test and fixture, and a line from the fixture to its test
+        for name in ["app::db", "app::errors", "app::fails", "app::passes"] {
+            assert!(
+                html.contains(&format!("<title>{name} (0.005s)</title>")),
+                "{name}"
+            );
+        }
+        assert_eq!(html.matches("<line ").count(), 1);
+    }
+
+    #[test]
+    fn lists_the_results_of_each_group() {
+        let mut reporter = HtmlReporter::new(Box::new(io::sink()));
+        report_run(&mut reporter);
+
+        assert_eq!(
+            reporter.results(),
+            r#"<details open><summary class="failed">app (3 tests, 2 failed)</summary>
+<table><tr><th>Test</th><th>Outcome</th><th>Duration</th></tr>
+<tr><td>errors</td><td class="errored">errored</td><td>0.005s</td></tr>
+<tr><td colspan="3"><pre>fixture `app::db` failed: no database</pre></td></tr>
+<tr><td colspan="3"><details><summary>output</summary><pre>to stdout
+</pre></details></td></tr>
+<tr><td>fails</td><td class="failed">failed</td><td>0.005s</td></tr>
+<tr><td colspan="3"><pre>panicked: assertion failed</pre></td></tr>
+<tr><td colspan="3"><details><summary>output</summary><pre>to stdout
+</pre></details></td></tr>
+<tr><td>passes</td><td class="passed">passed</td><td>0.005s</td></tr>
+<tr><td colspan="3"><details><summary>output</summary><pre>to stdout
+</pre></details></td></tr>
+</table></details>
+"#
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use uuid::Uuid;
+
+use super::{escape_xml, Event, Outcome, Output, Plan, Reporter, Summary};
+
+/// Writes a JUnit XML report once the run has finished, with a `testsuite` per module and the
+/// captured output of each test in `system-out` and `system-err`.
+pub struct JunitReporter {
+    writer: Box<dyn Write>,
+    plan: Plan,
+    results: HashMap<Uuid, (Outcome, Duration, Output)>,
+}
+
+impl JunitReporter {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        JunitReporter {
+            writer,
+            plan: Plan::default(),
+            results: HashMap::new(),
+        }
+    }
+
+    fn write_report(&mut self, summary: &Summary) -> io::Result<()> {
+        let w = &mut self.writer;
+
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<testsuites name="critic" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+            self.plan.tests.len(),
+            summary.failed,
+            summary.errored,
+            summary.duration.as_secs_f64()
+        )?;
+
+        let tests: HashMap<_, _> = self.plan.tests.iter().map(|test| (test.id, test)).collect();
+
+        // Every planned test finishes (those whose fixtures failed as errored), so none are skipped
+        for group in &self.plan.groups {
+            let results: Vec<_> = group
+                .tests
+                .iter()
+                .map(|id| (tests[id], &self.results[id]))
+                .collect();
+
+            let count = |f: fn(&Outcome) -> bool| {
+                results
+                    .iter()
+                    .filter(|(_, (outcome, ..))| f(outcome))
+                    .count()
+            };
+            let time: Duration = results.iter().map(|(_, (_, duration, _))| *duration).sum();
+
+            writeln!(
+                w,
+                r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+                escape_xml(&group.name),
+                results.len(),
+                count(|outcome| matches!(outcome, Outcome::Failed { .. })),
+                count(|outcome| matches!(outcome, Outcome::Errored { .. })),
+                time.as_secs_f64()
+            )?;
+
+            for (test, (outcome, duration, output)) in results {
+                write!(
+                    w,
+                    r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+                    escape_xml(&test.name),
+                    escape_xml(&test.module_path),
+                    duration.as_secs_f64()
+                )?;
+
+                if *outcome == Outcome::Passed && output.is_empty() {
+                    writeln!(w, "/>")?;
+                    continue;
+                }
+
+                writeln!(w, ">")?;
+                match outcome {
+                    Outcome::Passed => {}
+                    Outcome::Failed { message } => writeln!(
+                        w,
+                        r#"      <failure message="{}">{}</failure>"#,
+                        escape_xml(first_line(message)),
+                        escape_xml(message)
+                    )?,
+                    Outcome::Errored { message } => writeln!(
+                        w,
+                        r#"      <error message="{}">{}</error>"#,
+                        escape_xml(first_line(message)),
+                        escape_xml(message)
+                    )?,
+                }
+                // The log records follow the output as there is nowhere else for them
+                let mut stdout = output.stdout.clone();
+                for record in &output.records {
+                    stdout.push_str(&format!("{record}\n"));
+                }
+                if !stdout.is_empty() {
+                    writeln!(w, "      <system-out>{}</system-out>", escape_xml(&stdout))?;
+                }
+                if !output.stderr.is_empty() {
+                    writeln!(
+                        w,
+                        "      <system-err>{}</system-err>",
+                        escape_xml(&output.stderr)
+                    )?;
+                }
+                writeln!(w, "    </testcase>")?;
+            }
+
+            writeln!(w, "  </testsuite>")?;
+        }
+
+        writeln!(w, "</testsuites>")?;
+        w.flush()
+    }
+}
+
+impl Reporter for JunitReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        match event {
+            Event::RunStarted { plan, .. } => self.plan = (*plan).clone(),
+            Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output,
+            } => {
+                self.results
+                    .insert(test.id, ((*outcome).clone(), *duration, (*output).clone()));
+            }
+            Event::RunFinished { summary } => {
+                if let Err(err) = self.write_report(summary) {
+                    eprintln!("error: failed to write the JUnit report: {err}");
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::testing::{report_run, Buffer};
+
+    #[test]
+    fn writes_a_testsuite_per_module() {
+        let buffer = Buffer::default();
+        report_run(&mut JunitReporter::new(buffer.boxed()));
+
+        assert_eq!(
+            buffer.contents(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="critic" tests="3" failures="1" errors="1" time="0.020">
+  <testsuite name="app" tests="3" failures="1" errors="1" time="0.015">
+    <testcase name="errors" classname="app" time="0.005">
+      <error message="fixture `app::db` failed: no database">fixture `app::db` failed: no database</error>
+      <system-out>to stdout
+</system-out>
+    </testcase>
+    <testcase name="fails" classname="app" time="0.005">
+      <failure message="panicked: assertion failed">panicked: assertion failed</failure>
+      <system-out>to stdout
+</system-out>
+    </testcase>
+    <testcase name="passes" classname="app" time="0.005">
+      <system-out>to stdout
+</system-out>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::{self, Write};
+
+use serde_json::{json, Value};
+
+use super::{Event, Outcome, Reporter, TestInfo};
+use crate::options;
+
+/// Writes the JSON events of libtest (`--format json -Z unstable-options`) so that IDEs and other
+/// tools that understand libtest can show critic tests.
+///
+/// Fixtures have no equivalent in libtest, so are only visible through the tests they fail.
+pub struct LibtestJsonReporter {
+    writer: Box<dyn Write>,
+    filtered_out: usize,
+}
+
+impl LibtestJsonReporter {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        LibtestJsonReporter {
+            writer,
+            filtered_out: 0,
+        }
+    }
+
+    fn write_line(&mut self, value: &Value) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, value)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+
+/// The name libtest would give the test.
+fn libtest_name(test: &TestInfo) -> String {
+    options::libtest_name(&test.module_path, &test.name)
+}
+
+impl Reporter for LibtestJsonReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        let line = match event {
+            Event::RunStarted { run, plan } => {
+                self.filtered_out = run.filtered_out;
+
+                json!({
+                    "type": "suite",
+                    "event": "started",
+                    "test_count": plan.tests.len(),
+                })
+            }
+            Event::TestStarted { test, .. } => json!({
+                "type": "test",
+                "event": "started",
+                "name": libtest_name(test),
+            }),
+            Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output,
+            } => match outcome {
+                Outcome::Passed => json!({
+                    "type": "test",
+                    "name": libtest_name(test),
+                    "event": "ok",
+                    "exec_time": duration.as_secs_f64(),
+                }),
+                Outcome::Failed { message } | Outcome::Errored { message } => json!({
+                    "type": "test",
+                    "name": libtest_name(test),
+                    "event": "failed",
+                    "exec_time": duration.as_secs_f64(),
+                    // libtest puts everything the test printed (and the panic) in `stdout`
+                    "stdout": format!(
+                        "{}{}{}{message}\n",
+                        output.stdout,
+                        output.stderr,
+                        output.records.iter().map(|record| format!("{record}\n")).collect::<String>()
+                    ),
+                }),
+            },
+            Event::RunFinished { summary } => json!({
+                "type": "suite",
+                "event": if summary.success() { "ok" } else { "failed" },
+                "passed": summary.passed,
+                "failed": summary.failed + summary.errored,
+                "ignored": 0,
+                "measured": 0,
+                "filtered_out": self.filtered_out,
+                "exec_time": summary.duration.as_secs_f64(),
+            }),
+            Event::FixtureStarted { .. }
+            | Event::FixtureFinished { .. }
+            | Event::Scheduled { .. } => return,
+        };
+
+        if let Err(err) = self.write_line(&line) {
+            eprintln!("error: failed to write event: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::testing::{report_run, Buffer};
+
+    #[test]
+    fn writes_the_libtest_events() {
+        let buffer = Buffer::default();
+        report_run(&mut LibtestJsonReporter::new(buffer.boxed()));
+
+        let events: Vec<Value> = buffer
+            .contents()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            events,
+            [
+                json!({ "type": "suite", "event": "started", "test_count": 3 }),
+                json!({ "type": "test", "event": "started", "name": "errors" }),
+                json!({
+                    "type": "test",
+                    "name": "errors",
+                    "event": "failed",
+                    "exec_time": 0.005,
+                    "stdout": "to stdout\nfixture `app::db` failed: no database\n",
+                }),
+                json!({ "type": "test", "event": "started", "name": "fails" }),
+                json!({
+                    "type": "test",
+                    "name": "fails",
+                    "event": "failed",
+                    "exec_time": 0.005,
+                    "stdout": "to stdout\npanicked: assertion failed\n",
+                }),
+                json!({ "type": "test", "event": "started", "name": "passes" }),
+                json!({ "type": "test", "name": "passes", "event": "ok", "exec_time": 0.005 }),
+                json!({
+                    "type": "suite",
+                    "event": "failed",
+                    "passed": 1,
+                    "failed": 2,
+                    "ignored": 0,
+                    "measured": 0,
+                    "filtered_out": 0,
+                    "exec_time": 0.02,
+                }),
+            ]
+        );
+    }
+}
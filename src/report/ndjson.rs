@@ -0,0 +1,247 @@
+//! critic's own event stream, a JSON object per line.
+//!
+//! Every object has a `type`, the first is always `run_started` (which carries the
+//! `schema_version`) followed by the `plan`, the fixture and test events as they happen and finally
+//! a `summary`. Times and durations are in seconds, `elapsed` is measured from the start of the run.
+//! The `stdout`, `stderr` and `records` of finished events are empty when output isn't captured.
+//! Fields are only ever added within a schema version.
+
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+use serde_json::{json, Value};
+
+use super::{Event, Outcome, Record, Reporter, Transition};
+
+/// The version of the schema of the events, bumped whenever a change isn't backwards compatible.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Writes every event as a line of JSON, flushing after each so the stream can be followed live.
+pub struct NdjsonReporter {
+    writer: Box<dyn Write>,
+    start: Instant,
+}
+
+impl NdjsonReporter {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        NdjsonReporter {
+            writer,
+            start: Instant::now(),
+        }
+    }
+
+    fn write_line(&mut self, value: &Value) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, value)?;
+        writeln!(self.writer)?;
+        self.writer.flush()
+    }
+}
+
+fn outcome_fields(outcome: &Outcome) -> (&'static str, Option<&str>) {
+    match outcome {
+        Outcome::Passed => ("passed", None),
+        Outcome::Failed { message } => ("failed", Some(message)),
+        Outcome::Errored { message } => ("errored", Some(message)),
+    }
+}
+
+fn records(records: &[Record]) -> Vec<Value> {
+    records
+        .iter()
+        .map(|record| {
+            json!({
+                "level": record.level.as_str(),
+                "target": record.target,
+                "message": record.message,
+            })
+        })
+        .collect()
+}
+
+impl Reporter for NdjsonReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+
+        let lines = match event {
+            Event::RunStarted { run, plan } => {
+                self.start = Instant::now();
+
+                vec![
+                    json!({
+                        "type": "run_started",
+                        "schema_version": SCHEMA_VERSION,
+                        "critic_version": env!("CARGO_PKG_VERSION"),
+                        "threads": run.threads,
+                        "seed": run.seed,
+                        "filters": run.filters,
+                        "profile": run.profile,
+                    }),
+                    json!({
+                        "type": "plan",
+                        "groups": plan.groups.iter().map(|group| json!({
+                            "name": group.name,
+                            "tests": group.tests,
+                        })).collect::<Vec<_>>(),
+                        "tests": plan.tests.iter().map(|test| json!({
+                            "id": test.id,
+                            "module_path": test.module_path,
+                            "name": test.name,
+                            "fixtures": test.fixtures,
+                        })).collect::<Vec<_>>(),
+                        "fixtures": plan.fixtures.iter().map(|fixture| json!({
+                            "id": fixture.id,
+                            "module_path": fixture.module_path,
+                            "name": fixture.name,
+                            "type_name": fixture.type_name,
+                        })).collect::<Vec<_>>(),
+                    }),
+                ]
+            }
+            Event::FixtureStarted { fixture, worker } => vec![json!({
+                "type": "fixture_started",
+                "id": fixture.id,
+                "name": fixture.fqn(),
+                "worker": worker,
+                "elapsed": elapsed,
+            })],
+            Event::FixtureFinished {
+                fixture,
+                outcome,
+                duration,
+                output,
+            } => {
+                let (outcome, message) = outcome_fields(outcome);
+                vec![json!({
+                    "type": "fixture_finished",
+                    "id": fixture.id,
+                    "name": fixture.fqn(),
+                    "outcome": outcome,
+                    "message": message,
+                    "duration": duration.as_secs_f64(),
+                    "elapsed": elapsed,
+                    "stdout": output.stdout,
+                    "stderr": output.stderr,
+                    "records": records(&output.records),
+                })]
+            }
+            Event::TestStarted { test, worker } => vec![json!({
+                "type": "test_started",
+                "id": test.id,
+                "name": test.fqn(),
+                "worker": worker,
+                "elapsed": elapsed,
+            })],
+            Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output,
+            } => {
+                let (outcome, message) = outcome_fields(outcome);
+                vec![json!({
+                    "type": "test_finished",
+                    "id": test.id,
+                    "name": test.fqn(),
+                    "outcome": outcome,
+                    "message": message,
+                    "duration": duration.as_secs_f64(),
+                    "elapsed": elapsed,
+                    "stdout": output.stdout,
+                    "stderr": output.stderr,
+                    "records": records(&output.records),
+                })]
+            }
+            Event::Scheduled { id, transition } => vec![match transition {
+                Transition::Excluded { by } => json!({
+                    "type": "excluded",
+                    "id": id,
+                    "by": by,
+                    "elapsed": elapsed,
+                }),
+                Transition::Ready => json!({
+                    "type": "ready",
+                    "id": id,
+                    "elapsed": elapsed,
+                }),
+            }],
+            Event::RunFinished { summary } => vec![json!({
+                "type": "summary",
+                "passed": summary.passed,
+                "failed": summary.failed,
+                "errored": summary.errored,
+                "duration": summary.duration.as_secs_f64(),
+            })],
+        };
+
+        for line in &lines {
+            if let Err(err) = self.write_line(line) {
+                eprintln!("error: failed to write event: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::testing::{report_run, Buffer};
+
+    #[test]
+    fn writes_an_object_per_event() {
+        let buffer = Buffer::default();
+        report_run(&mut NdjsonReporter::new(buffer.boxed()));
+
+        let events: Vec<Value> = buffer
+            .contents()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let types: Vec<_> = events
+            .iter()
+            .map(|event| event["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "run_started",
+                "plan",
+                "fixture_started",
+                "fixture_finished",
+                "test_started",
+                "test_finished",
+                "test_started",
+                "test_finished",
+                "test_started",
+                "test_finished",
+                "summary",
+            ]
+        );
+
+        assert_eq!(events[0]["schema_version"], SCHEMA_VERSION);
+        assert_eq!(events[0]["threads"], 1);
+        assert_eq!(events[3]["outcome"], "failed");
+        assert_eq!(events[3]["message"], "no database");
+
+        let errored = &events[5];
+        assert_eq!(errored["name"], "app::errors");
+        assert_eq!(errored["outcome"], "errored");
+        assert_eq!(errored["message"], "fixture `app::db` failed: no database");
+        assert_eq!(errored["duration"], 0.005);
+        assert_eq!(errored["stdout"], "to stdout\n");
+        assert_eq!(events[9]["outcome"], "passed");
+        assert_eq!(events[9]["message"], Value::Null);
+
+        assert_eq!(
+            events[10],
+            json!({
+                "type": "summary",
+                "passed": 1,
+                "failed": 1,
+                "errored": 1,
+                "duration": 0.02,
+            })
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    env,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::Outcome;
+use crate::engine::capture;
+
+/// How often the region is redrawn while nothing else happens.
+const TICK: Duration = Duration::from_millis(100);
+
+/// A region at the bottom of the terminal showing the counts so far, an estimate of the time left
+/// and what is running right now (with how long it has been running for).
+///
+/// The region is redrawn from a background thread so that the times keep moving during long tests.
+pub(super) struct Progress {
+    state: Arc<Mutex<State>>,
+    ticker: Option<JoinHandle<()>>,
+}
+
+struct State {
+    start: Instant,
+    total: usize,
+    passed: usize,
+    failed: usize,
+    errored: usize,
+    /// The total time spent in the tests that finished
+    test_time: Duration,
+    running: Vec<(String, Instant)>,
+    /// The number of lines of the region currently on the screen
+    drawn: usize,
+    finished: bool,
+    /// The terminal, which stays put while the output of tests is captured
+    out: Box<dyn Write + Send>,
+}
+
+impl State {
+    fn completed(&self) -> usize {
+        self.passed + self.failed + self.errored
+    }
+
+    /// The time left, estimated from the mean time of the tests so far.
+    fn eta(&self) -> Option<Duration> {
+        let completed = self.completed();
+        if completed == 0 {
+            return None;
+        }
+
+        let remaining = self.total.saturating_sub(completed) as u32;
+        Some(self.test_time / completed as u32 * remaining)
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let eta = match self.eta() {
+            Some(eta) => format!("{:.1}s", eta.as_secs_f64()),
+            None => "-".to_string(),
+        };
+
+        let mut lines = vec![format!(
+            "[{}/{}] {} passed, {} failed, {} errored; {:.1}s elapsed, ETA {eta}",
+            self.completed(),
+            self.total,
+            self.passed,
+            self.failed,
+            self.errored,
+            self.start.elapsed().as_secs_f64(),
+        )];
+        lines.extend(
+            self.running
+                .iter()
+                .map(|(name, start)| format!("    {name} ({:.1}s)", start.elapsed().as_secs_f64())),
+        );
+
+        let width = terminal_width();
+        lines
+            .into_iter()
+            .map(|line| line.chars().take(width).collect())
+            .collect()
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        if self.drawn > 0 {
+            write!(self.out, "\x1b[{}A\r\x1b[J", self.drawn)?;
+            self.drawn = 0;
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        self.clear()?;
+        if self.finished {
+            return self.out.flush();
+        }
+
+        let lines = self.lines();
+        for line in &lines {
+            writeln!(self.out, "{line}")?;
+        }
+        self.drawn = lines.len();
+        self.out.flush()
+    }
+}
+
+/// The width of the terminal, lines longer than this would wrap and break the redrawing.
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80)
+}
+
+impl Progress {
+    pub(super) fn start(total: usize) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            start: Instant::now(),
+            total,
+            passed: 0,
+            failed: 0,
+            errored: 0,
+            test_time: Duration::ZERO,
+            running: Vec::new(),
+            drawn: 0,
+            finished: false,
+            out: capture::terminal(),
+        }));
+
+        let ticker = {
+            let state = Arc::clone(&state);
+            thread::spawn(move || loop {
+                thread::sleep(TICK);
+
+                let mut state = state.lock().unwrap();
+                if state.finished {
+                    break;
+                }
+                let _ = state.draw();
+            })
+        };
+
+        Progress {
+            state,
+            ticker: Some(ticker),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let mut state = self.state.lock().unwrap();
+        f(&mut state);
+        let _ = state.draw();
+    }
+
+    pub(super) fn started(&self, name: String) {
+        self.update(|state| state.running.push((name, Instant::now())));
+    }
+
+    /// Removes `name` from the running nodes, counting it if it was a test.
+    pub(super) fn finished(&self, name: &str, test: Option<(&Outcome, Duration)>) {
+        self.update(|state| {
+            state.running.retain(|(running, _)| running != name);
+
+            if let Some((outcome, duration)) = test {
+                state.test_time += duration;
+                match outcome {
+                    Outcome::Passed => state.passed += 1,
+                    Outcome::Failed { .. } => state.failed += 1,
+                    Outcome::Errored { .. } => state.errored += 1,
+                }
+            }
+        });
+    }
+
+    /// Prints `text` above the region.
+    pub(super) fn println(&self, text: &str) {
+        let mut state = self.state.lock().unwrap();
+        let _ = state.clear();
+        let _ = writeln!(state.out, "{text}");
+        let _ = state.draw();
+    }
+
+    /// Removes the region for good.
+    pub(super) fn finish(&mut self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.finished = true;
+            let _ = state.draw();
+        }
+
+        if let Some(ticker) = self.ticker.take() {
+            let _ = ticker.join();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_each_outcome() {
+        let state = State {
+            start: Instant::now(),
+            total: 4,
+            passed: 1,
+            failed: 1,
+            errored: 1,
+            test_time: Duration::from_secs(3),
+            running: vec![("app::slow".to_string(), Instant::now())],
+            drawn: 0,
+            finished: false,
+            out: Box::new(io::sink()),
+        };
+
+        let lines = state.lines();
+        assert!(
+            lines[0].starts_with("[3/4] 1 passed, 1 failed, 1 errored;"),
+            "{lines:?}"
+        );
+        assert!(lines[0].ends_with("ETA 1.0s"), "{lines:?}");
+        assert!(lines[1].starts_with("    app::slow ("), "{lines:?}");
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, Write};
+
+use super::{Event, Outcome, Reporter};
+
+/// Writes the results in the Test Anything Protocol (version 14) as the tests finish.
+pub struct TapReporter {
+    writer: Box<dyn Write>,
+    /// The number of the last test point that was written
+    count: usize,
+}
+
+impl TapReporter {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        TapReporter { writer, count: 0 }
+    }
+
+    fn write_event(&mut self, event: &Event<'_>) -> io::Result<()> {
+        let w = &mut self.writer;
+
+        match event {
+            Event::RunStarted { plan, .. } => {
+                writeln!(w, "TAP version 14")?;
+                writeln!(w, "1..{}", plan.tests.len())?;
+            }
+            Event::TestFinished {
+                test,
+                outcome,
+                duration,
+                output,
+            } => {
+                self.count += 1;
+
+                let (message, severity) = match outcome {
+                    Outcome::Passed => {
+                        writeln!(w, "ok {} - {}", self.count, test.fqn())?;
+                        return w.flush();
+                    }
+                    Outcome::Failed { message } => (message, "fail"),
+                    Outcome::Errored { message } => (message, "error"),
+                };
+
+                writeln!(w, "not ok {} - {}", self.count, test.fqn())?;
+                writeln!(w, "  ---")?;
+                writeln!(w, "  message: |")?;
+                for line in message.lines() {
+                    writeln!(w, "    {line}")?;
+                }
+                writeln!(w, "  severity: {severity}")?;
+                writeln!(w, "  duration_ms: {:.3}", duration.as_secs_f64() * 1000.0)?;
+                for (stream, captured) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+                    if !captured.is_empty() {
+                        writeln!(w, "  {stream}: |")?;
+                        for line in captured.lines() {
+                            writeln!(w, "    {line}")?;
+                        }
+                    }
+                }
+                if !output.records.is_empty() {
+                    writeln!(w, "  records:")?;
+                    for record in &output.records {
+                        writeln!(w, "    - {:?}", record.to_string())?;
+                    }
+                }
+                writeln!(w, "  ...")?;
+            }
+            _ => return Ok(()),
+        }
+
+        w.flush()
+    }
+}
+
+impl Reporter for TapReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        if let Err(err) = self.write_event(event) {
+            eprintln!("error: failed to write TAP output: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::testing::{report_run, Buffer};
+
+    #[test]
+    fn writes_a_test_point_per_test() {
+        let buffer = Buffer::default();
+        report_run(&mut TapReporter::new(buffer.boxed()));
+
+        assert_eq!(
+            buffer.contents(),
+            "\
+TAP version 14
+1..3
+not ok 1 - app::errors
+  ---
+  message: |
+    fixture `app::db` failed: no database
+  severity: error
+  duration_ms: 5.000
+  stdout: |
+    to stdout
+  ...
+not ok 2 - app::fails
+  ---
+  message: |
+    panicked: assertion failed
+  severity: fail
+  duration_ms: 5.000
+  stdout: |
+    to stdout
+  ...
+ok 3 - app::passes
+"
+        );
+    }
+}
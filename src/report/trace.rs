@@ -0,0 +1,255 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{self, Write},
+    time::Instant,
+};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{Event, Outcome, Reporter, Transition};
+
+/// The process id used for every event (there is only ever one process).
+const PID: u32 = 1;
+
+/// Writes the run in the Chrome Trace Event format once it has finished, to be opened with
+/// `chrome://tracing` or Perfetto.
+///
+/// Every test and fixture is a slice on the worker that ran it, and every change in the schedule
+/// (a node being excluded by others or becoming ready once its fixtures completed) is an instant
+/// event, so the gaps between slices can be explained.
+pub struct TraceReporter {
+    writer: Box<dyn Write>,
+    start: Instant,
+    names: HashMap<Uuid, String>,
+    workers: BTreeSet<usize>,
+    events: Vec<Value>,
+}
+
+impl TraceReporter {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        TraceReporter {
+            writer,
+            start: Instant::now(),
+            names: HashMap::new(),
+            workers: BTreeSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// The time since the start of the run in microseconds.
+    fn timestamp(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1_000_000.0
+    }
+
+    fn name(&self, id: &Uuid) -> String {
+        self.names
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    fn start_slice(&mut self, id: Uuid, category: &str, worker: usize) {
+        self.workers.insert(worker);
+        self.events.push(json!({
+            "name": self.name(&id),
+            "cat": category,
+            "ph": "B",
+            "ts": self.timestamp(),
+            "pid": PID,
+            "tid": worker,
+            "args": { "id": id },
+        }));
+    }
+
+    /// Turns the `B` event of the slice into a complete `X` event now that its duration is known.
+    fn finish_slice(&mut self, id: Uuid, outcome: &Outcome) {
+        let now = self.timestamp();
+        let id = json!(id);
+        let Some(event) = self
+            .events
+            .iter_mut()
+            .rev()
+            .find(|event| event["ph"] == "B" && event["args"]["id"] == id)
+        else {
+            return;
+        };
+
+        let start = event["ts"].as_f64().unwrap_or(now);
+        event["ph"] = json!("X");
+        event["dur"] = json!(now - start);
+        event["args"]["outcome"] = json!(match outcome {
+            Outcome::Passed => "passed",
+            Outcome::Failed { .. } => "failed",
+            Outcome::Errored { .. } => "errored",
+        });
+        if let Outcome::Failed { message } | Outcome::Errored { message } = outcome {
+            event["args"]["message"] = json!(message);
+        }
+    }
+
+    fn write_trace(&mut self) -> io::Result<()> {
+        let metadata = self.workers.iter().map(|worker| {
+            json!({
+                "name": "thread_name",
+                "ph": "M",
+                "pid": PID,
+                "tid": worker,
+                "args": { "name": format!("worker {worker}") },
+            })
+        });
+        let process = json!({
+            "name": "process_name",
+            "ph": "M",
+            "pid": PID,
+            "args": { "name": "critic" },
+        });
+
+        let events: Vec<_> = std::iter::once(process)
+            .chain(metadata)
+            .chain(self.events.drain(..))
+            .collect();
+
+        serde_json::to_writer(
+            &mut self.writer,
+            &json!({ "traceEvents": events, "displayTimeUnit": "ms" }),
+        )?;
+        self.writer.flush()
+    }
+}
+
+impl Reporter for TraceReporter {
+    fn report(&mut self, event: &Event<'_>) {
+        match event {
+            Event::RunStarted { plan, .. } => {
+                self.start = Instant::now();
+                self.names = plan
+                    .tests
+                    .iter()
+                    .map(|test| (test.id, test.fqn()))
+                    .chain(
+                        plan.fixtures
+                            .iter()
+                            .map(|fixture| (fixture.id, fixture.fqn())),
+                    )
+                    .collect();
+            }
+            Event::FixtureStarted { fixture, worker } => {
+                self.start_slice(fixture.id, "fixture", *worker)
+            }
+            Event::TestStarted { test, worker } => self.start_slice(test.id, "test", *worker),
+            Event::FixtureFinished {
+                fixture, outcome, ..
+            } => self.finish_slice(fixture.id, outcome),
+            Event::TestFinished { test, outcome, .. } => self.finish_slice(test.id, outcome),
+            // The start of the schedule isn't a test or fixture
+            Event::Scheduled { id, .. } if !self.names.contains_key(id) => {}
+            Event::Scheduled { id, transition } => {
+                // The worker is only known once the node starts, so these are process wide
+                let (name, args) = match transition {
+                    Transition::Excluded { by } => (
+                        format!("{} excluded", self.name(id)),
+                        json!({
+                            "id": id,
+                            "by": by.iter().map(|id| self.name(id)).collect::<Vec<_>>(),
+                        }),
+                    ),
+                    Transition::Ready => (format!("{} ready", self.name(id)), json!({ "id": id })),
+                };
+
+                self.events.push(json!({
+                    "name": name,
+                    "cat": "schedule",
+                    "ph": "i",
+                    "s": "p",
+                    "ts": self.timestamp(),
+                    "pid": PID,
+                    "args": args,
+                }));
+            }
+            Event::RunFinished { .. } => {
+                if let Err(err) = self.write_trace() {
+                    eprintln!("error: failed to write the trace: {err}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::testing::{report_run, Buffer};
+
+    fn trace_events(buffer: &Buffer) -> Vec<Value> {
+        let trace: Value = serde_json::from_str(&buffer.contents()).unwrap();
+        trace["traceEvents"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn writes_a_slice_per_test_and_fixture() {
+        let buffer = Buffer::default();
+        report_run(&mut TraceReporter::new(buffer.boxed()));
+        let events = trace_events(&buffer);
+
+        assert_eq!(events[0]["args"]["name"], "critic");
+        assert_eq!(events[1]["args"]["name"], "worker 0");
+
+        let slices: Vec<_> = events[2..]
+            .iter()
+            .map(|event| {
+                assert_eq!(event["ph"], "X");
+                assert_eq!(event["tid"], 0);
+                (
+                    event["name"].as_str().unwrap(),
+                    event["cat"].as_str().unwrap(),
+                    event["args"]["outcome"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            slices,
+            [
+                ("app::db", "fixture", "failed"),
+                ("app::errors", "test", "errored"),
+                ("app::fails", "test", "failed"),
+                ("app::passes", "test", "passed"),
+            ]
+        );
+        assert_eq!(events[2]["args"]["message"], "no database");
+    }
+
+    #[test]
+    fn writes_an_instant_per_transition() {
+        let buffer = Buffer::default();
+        let mut reporter = TraceReporter::new(buffer.boxed());
+        let (reads, writes, start) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        reporter.names = HashMap::from([
+            (reads, "app::reads".to_string()),
+            (writes, "app::writes".to_string()),
+        ]);
+
+        let excluded = Transition::Excluded { by: vec![writes] };
+        for (id, transition) in [
+            (start, &Transition::Ready),
+            (reads, &excluded),
+            (reads, &Transition::Ready),
+        ] {
+            reporter.report(&Event::Scheduled { id, transition });
+        }
+        reporter.write_trace().unwrap();
+
+        let instants: Vec<_> = trace_events(&buffer)
+            .into_iter()
+            .filter(|event| event["ph"] == "i")
+            .map(|event| (event["name"].clone(), event["args"]["by"].clone()))
+            .collect();
+        assert_eq!(
+            instants,
+            [
+                (json!("app::reads excluded"), json!(["app::writes"])),
+                (json!("app::reads ready"), Value::Null),
+            ]
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! The diagnostics of `#[critic::test]` and `#[critic::fixture]` for the code they reject, and the
+//! code they accept that is easy to reject by mistake.
+
+#[test]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+    cases.pass("tests/ui/pass/*.rs");
+}
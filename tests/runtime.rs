@@ -0,0 +1,126 @@
+//! Tests that run through the critic engine itself (`harness = false`), every test has to pass.
+
+use std::sync::Arc;
+
+struct Counter {
+    count: u32,
+}
+
+#[critic::fixture]
+fn counter() -> Counter {
+    Counter { count: 0 }
+}
+
+struct Wrapper(String);
+
+#[critic::fixture]
+fn wrapper() -> Wrapper {
+    Wrapper("wrapped".to_string())
+}
+
+trait Greeting {
+    fn greet(&self) -> String;
+}
+
+struct English;
+
+impl Greeting for English {
+    fn greet(&self) -> String {
+        "hello".to_string()
+    }
+}
+
+#[critic::fixture(provides = dyn Greeting)]
+fn english() -> English {
+    English
+}
+
+critic::critic_test_main!();
+
+#[critic::test]
+fn owned_input_is_a_fresh_instance(mut counter: Counter) {
+    counter.count += 1;
+    assert_eq!(counter.count, 1);
+}
+
+#[critic::test]
+fn owned_input_is_not_shared(mut counter: Counter, shared: &Counter) {
+    counter.count += 1;
+    assert_eq!((counter.count, shared.count), (1, 0));
+}
+
+#[critic::test]
+fn destructures_owned_input(Wrapper(inner): Wrapper) {
+    assert_eq!(inner, "wrapped");
+}
+
+#[critic::test]
+fn destructures_borrowed_input(Wrapper(inner): &Wrapper) {
+    assert_eq!(inner, "wrapped");
+}
+
+/// Nothing provides this
+struct Missing;
+
+#[critic::test]
+fn optional_input_is_provided(
+    counter: Option<Counter>,
+    shared: Option<&Counter>,
+    exclusive: Option<&mut Wrapper>,
+    arc: Option<Arc<Counter>>,
+) {
+    assert_eq!(counter.map(|counter| counter.count), Some(0));
+    assert_eq!(arc.map(|counter| counter.count), Some(0));
+    assert_eq!(shared.map(|counter| counter.count), Some(0));
+    assert_eq!(
+        exclusive.map(|Wrapper(inner)| inner.as_str()),
+        Some("wrapped")
+    );
+}
+
+#[critic::test]
+fn optional_input_is_missing(
+    owned: Option<Missing>,
+    shared: Option<&Missing>,
+    exclusive: Option<&mut Missing>,
+    arc: Option<Arc<Missing>>,
+) {
+    assert!(owned.is_none() && shared.is_none() && exclusive.is_none() && arc.is_none());
+}
+
+#[critic::test]
+fn trait_object_is_injected(greeting: &dyn Greeting, kept: Arc<dyn Greeting>) {
+    assert_eq!(
+        (greeting.greet(), kept.greet()),
+        ("hello".into(), "hello".into())
+    );
+}
+
+#[critic::test]
+fn factory_creates_independent_instances(counters: critic::Factory<Counter>) {
+    let mut first = counters.create();
+    let second = counters.create();
+    first.count += 1;
+
+    assert_eq!((first.count, second.count), (1, 0));
+}
+
+#[critic::test(types(u8, std::num::Wrapping<u8>))]
+fn roundtrip<T: Default + PartialEq + std::fmt::Debug>() {
+    assert_eq!(T::default(), T::default());
+}
+
+#[critic::test]
+fn generic_test_is_registered_per_type() {
+    let mut names: Vec<_> = __critic_test_internals::CRITIC_INTERNAL_TESTS
+        .iter()
+        .map(|config| config().name)
+        .filter(|name| name.starts_with("roundtrip"))
+        .collect();
+    names.sort();
+
+    assert_eq!(
+        names,
+        ["roundtrip::<std::num::Wrapping<u8>>", "roundtrip::<u8>"]
+    );
+}
@@ -0,0 +1,4 @@
+#[critic::test]
+async fn waits() {}
+
+critic::critic_test_main!();
@@ -0,0 +1,8 @@
+struct MyFixture;
+
+#[critic::fixture]
+fn produce(value: u32) -> MyFixture {
+    MyFixture
+}
+
+critic::critic_test_main!();
@@ -0,0 +1,11 @@
+struct MyFixture;
+
+#[critic::fixture(name = "replica")]
+fn produce() -> MyFixture {
+    MyFixture
+}
+
+#[critic::test]
+fn uses_replica(#[fixture(replica)] fixture: &MyFixture) {}
+
+critic::critic_test_main!();
@@ -0,0 +1,4 @@
+#[critic::fixture]
+fn produce() {}
+
+critic::critic_test_main!();
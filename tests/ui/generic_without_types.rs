@@ -0,0 +1,4 @@
+#[critic::test]
+fn generic<T: Default>() {}
+
+critic::critic_test_main!();
@@ -0,0 +1,4 @@
+#[critic::test]
+fn uses_impl_trait(value: &impl std::fmt::Debug) {}
+
+critic::critic_test_main!();
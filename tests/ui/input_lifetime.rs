@@ -0,0 +1,11 @@
+struct MyFixture;
+
+#[critic::fixture]
+fn produce() -> MyFixture {
+    MyFixture
+}
+
+#[critic::test]
+fn uses_lifetime<'a>(fixture: &'a MyFixture) {}
+
+critic::critic_test_main!();
@@ -0,0 +1,25 @@
+//! Types only provided by an override or a profile fixture compile.
+
+struct Cache;
+
+#[critic::fixture(profile = "cache")]
+fn cache() -> Cache {
+    Cache
+}
+
+#[critic::test]
+fn uses_the_cache_when_available(_cache: Option<&Cache>) {}
+
+mod overridden {
+    pub struct Clock;
+
+    #[critic::fixture(override)]
+    fn clock() -> Clock {
+        Clock
+    }
+
+    #[critic::test]
+    fn uses_the_override(_clock: &Clock) {}
+}
+
+critic::critic_test_main!();
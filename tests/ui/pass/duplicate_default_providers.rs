@@ -0,0 +1,28 @@
+//! Two default providers of one type compile, the consumers that would find them ambiguous are
+//! reported by the validation at run time.
+
+struct Db;
+
+#[critic::fixture]
+fn primary() -> Db {
+    Db
+}
+
+#[critic::fixture]
+fn secondary() -> Db {
+    Db
+}
+
+mod overridden {
+    use crate::Db;
+
+    #[critic::fixture(override)]
+    fn local() -> Db {
+        Db
+    }
+
+    #[critic::test]
+    fn uses_the_override(_db: &Db) {}
+}
+
+critic::critic_test_main!();
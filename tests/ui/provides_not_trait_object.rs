@@ -0,0 +1,8 @@
+struct MyFixture;
+
+#[critic::fixture(provides = MyFixture)]
+fn produce() -> MyFixture {
+    MyFixture
+}
+
+critic::critic_test_main!();
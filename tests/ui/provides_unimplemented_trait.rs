@@ -0,0 +1,12 @@
+trait Clock {
+    fn now(&self) -> u64;
+}
+
+struct NotAClock;
+
+#[critic::fixture(provides = dyn Clock)]
+fn not_a_clock() -> NotAClock {
+    NotAClock
+}
+
+critic::critic_test_main!();
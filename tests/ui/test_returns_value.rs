@@ -0,0 +1,6 @@
+#[critic::test]
+fn returns() -> Result<(), String> {
+    Ok(())
+}
+
+critic::critic_test_main!();
@@ -0,0 +1,8 @@
+struct Suite;
+
+impl Suite {
+    #[critic::test]
+    fn method(&self) {}
+}
+
+critic::critic_test_main!();
@@ -0,0 +1,4 @@
+#[critic::test(types(u32))]
+fn not_generic() {}
+
+critic::critic_test_main!();
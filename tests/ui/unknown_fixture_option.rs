@@ -0,0 +1,8 @@
+struct MyFixture;
+
+#[critic::fixture(named = "replica")]
+fn produce() -> MyFixture {
+    MyFixture
+}
+
+critic::critic_test_main!();
@@ -0,0 +1,4 @@
+#[critic::test(should_panic)]
+fn panics() {}
+
+critic::critic_test_main!();